@@ -1,7 +1,6 @@
 // This example is not going to build in this folder.
 // You need to copy this code into your project and add the dependencies whisper_rs and hound in your cargo.toml
 
-use hound;
 use std::fs::File;
 use std::io::Write;
 use whisper_rs::{FullParams, SamplingStrategy, WhisperContext};
@@ -14,8 +13,8 @@ fn main() -> Result<(), &'static str> {
 
     // Create a params object for running the model.
     // Currently, only the Greedy sampling strategy is implemented, with BeamSearch as a WIP.
-    // The number of past samples to consider defaults to 0.
-    let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 0 });
+    // best_of must be at least 1.
+    let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
 
     // Edit params as needed.
     // Set the number of threads to use to 1.
@@ -0,0 +1,165 @@
+//! Transcribing many inputs at once, spread across the contexts of a [WhisperContextPool].
+//!
+//! ```no_run
+//! use whisper_rs::{batch, FullParams, SamplingStrategy, WhisperContextPool};
+//!
+//! let pool = WhisperContextPool::new("model.bin", 4).expect("failed to load model");
+//! let inputs: Vec<Vec<f32>> = vec![vec![0.0; 16000]; 16];
+//! let transcripts = batch::transcribe_all(&pool, &inputs, 4, || {
+//!     let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+//!     params.set_n_threads(1);
+//!     params
+//! });
+//! for transcript in transcripts {
+//!     println!("{}", transcript.expect("failed to transcribe").text());
+//! }
+//! ```
+
+use crate::{FullParams, Transcript, WhisperContextPool, WhisperError};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Transcribe every input, running up to `parallelism` of them at the same time.
+///
+/// Each worker thread borrows one context from the pool for the whole batch,
+/// and takes the next input from a shared queue whenever it finishes one.
+/// Consider lowering the number of threads per input with [FullParams::set_n_threads]
+/// to avoid oversubscribing the CPU.
+///
+/// Don't hold on to any [PooledContext](crate::PooledContext) of the same pool while this runs:
+/// the workers wait for the contexts they need, so if none of them are returned, this never finishes.
+///
+/// # Arguments
+/// * pool: The contexts to run the model with.
+/// * inputs: PCM audio data of every input.
+/// * parallelism: Maximum number of inputs to transcribe at the same time.
+///   At most [WhisperContextPool::size] are, as every worker needs its own context.
+/// * make_params: Called to create the parameters for each input.
+///
+/// # Returns
+/// The transcript of every input, or the error transcribing it failed with, in the same order as `inputs`.
+/// Every input fails with [WhisperError::EmptyPool] if the pool has no contexts.
+pub fn transcribe_all<A, F>(
+    pool: &WhisperContextPool,
+    inputs: &[A],
+    parallelism: usize,
+    make_params: F,
+) -> Vec<Result<Transcript, WhisperError>>
+where
+    A: AsRef<[f32]> + Sync,
+    F: Fn() -> FullParams + Sync,
+{
+    if pool.size() == 0 {
+        return inputs
+            .iter()
+            .map(|_| Err(WhisperError::EmptyPool))
+            .collect();
+    }
+    let workers = worker_count(parallelism, pool.size(), inputs.len());
+    run_queue(
+        inputs.len(),
+        workers,
+        || pool.get(),
+        |ctx, index| {
+            ctx.full(make_params(), inputs[index].as_ref())
+                .and_then(|_| ctx.collect_transcript())
+        },
+    )
+}
+
+/// Number of workers to run: as many as requested, but no more than there are contexts or inputs, and at least one.
+fn worker_count(parallelism: usize, pool_size: usize, n_inputs: usize) -> usize {
+    parallelism.min(pool_size).min(n_inputs).max(1)
+}
+
+/// Process the inputs `0..n_inputs` on `workers` threads, taking the next one from a shared queue.
+///
+/// Every worker calls `start` once before processing its first input, e.g. to borrow a context.
+/// Workers that would start after the queue is empty don't call it.
+///
+/// # Returns
+/// The result of processing every input, in order.
+fn run_queue<W, R, S, P>(n_inputs: usize, workers: usize, start: S, process: P) -> Vec<R>
+where
+    R: Send,
+    S: Fn() -> W + Sync,
+    P: Fn(&mut W, usize) -> R + Sync,
+{
+    let next = AtomicUsize::new(0);
+
+    let mut results: Vec<(usize, R)> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut results = Vec::new();
+                    let mut index = next.fetch_add(1, Ordering::Relaxed);
+                    if index >= n_inputs {
+                        return results;
+                    }
+                    let mut worker = start();
+                    while index < n_inputs {
+                        results.push((index, process(&mut worker, index)));
+                        index = next.fetch_add(1, Ordering::Relaxed);
+                    }
+                    results
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| match handle.join() {
+                Ok(results) => results,
+                Err(panic) => std::panic::resume_unwind(panic),
+            })
+            .collect()
+    });
+
+    results.sort_unstable_by_key(|&(index, _)| index);
+    results.into_iter().map(|(_, ret)| ret).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn assert_worker_count() {
+        assert_eq!(worker_count(8, 2, 10), 2);
+        assert_eq!(worker_count(2, 8, 10), 2);
+        assert_eq!(worker_count(8, 8, 3), 3);
+        assert_eq!(worker_count(0, 8, 10), 1);
+        assert_eq!(worker_count(8, 8, 0), 1);
+    }
+
+    #[test]
+    fn assert_queue_keeps_order() {
+        let started = AtomicUsize::new(0);
+        let results = run_queue(
+            20,
+            3,
+            || started.fetch_add(1, Ordering::Relaxed),
+            |&mut worker, index| {
+                // finish out of order
+                std::thread::sleep(Duration::from_millis(((20 - index) % 4) as u64));
+                (index * 2, worker)
+            },
+        );
+        let values: Vec<usize> = results.iter().map(|&(value, _)| value).collect();
+        assert_eq!(values, (0..20).map(|i| i * 2).collect::<Vec<_>>());
+        assert_eq!(started.load(Ordering::Relaxed), 3);
+        assert!(results.iter().all(|&(_, worker)| worker < 3));
+
+        assert!(run_queue(0, 1, || (), |_, index| index).is_empty());
+    }
+
+    #[test]
+    fn assert_empty_pool_fails() {
+        let pool = WhisperContextPool::from_contexts(Vec::new());
+        let inputs = vec![vec![0.0; 16000]; 2];
+        let results = transcribe_all(&pool, &inputs, 4, || unreachable!());
+        assert_eq!(results.len(), 2);
+        assert!(results
+            .iter()
+            .all(|ret| matches!(ret, Err(WhisperError::EmptyPool))));
+    }
+}
@@ -0,0 +1,196 @@
+//! Command line transcription of WAV files, modelled after whisper.cpp's `main` example.
+//!
+//! Only built with the `cli` feature: `cargo install whisper-rs --features cli`.
+
+use clap::{Parser, ValueEnum};
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use whisper_rs::{
+    ConsoleOutput, FullParamsBuilder, SamplingStrategy, Task, Transcript, WhisperContext,
+};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    Txt,
+    Srt,
+    Vtt,
+    Lrc,
+    Ttml,
+    Ass,
+    Csv,
+    Tsv,
+    Json,
+}
+
+impl OutputFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            Self::Txt => "txt",
+            Self::Srt => "srt",
+            Self::Vtt => "vtt",
+            Self::Lrc => "lrc",
+            Self::Ttml => "ttml",
+            Self::Ass => "ass",
+            Self::Csv => "csv",
+            Self::Tsv => "tsv",
+            Self::Json => "json",
+        }
+    }
+
+    /// Does the format need the times of individual words?
+    fn word_timed(&self) -> bool {
+        matches!(self, Self::Lrc | Self::Ttml | Self::Ass)
+    }
+}
+
+/// Transcribe 16 kHz WAV files with a ggml Whisper model.
+#[derive(Debug, Parser)]
+#[command(version)]
+struct Args {
+    /// Path to the ggml model file.
+    #[arg(short, long)]
+    model: PathBuf,
+    /// WAV files to transcribe. They must be 16 kHz, mono or stereo.
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+    /// Spoken language, or "auto" to detect it.
+    #[arg(short, long, default_value = "en")]
+    language: String,
+    /// Number of threads to use.
+    #[arg(short, long, default_value_t = whisper_rs::default_n_threads())]
+    threads: i32,
+    /// Translate to English instead of transcribing.
+    #[arg(long)]
+    translate: bool,
+    /// Number of beams for beam search. Greedy sampling is used if not set.
+    #[arg(long)]
+    beam_size: Option<i32>,
+    /// Output format.
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Txt)]
+    format: OutputFormat,
+    /// Write the output next to each input file, with the extension of the format,
+    /// instead of printing it.
+    #[arg(long)]
+    output_files: bool,
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    let mut ctx = match WhisperContext::new(&args.model.to_string_lossy()) {
+        Ok(ctx) => ctx,
+        Err(e) => {
+            eprintln!("failed to load model {}: {}", args.model.display(), e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut failed = false;
+    for file in &args.files {
+        if let Err(e) = transcribe_file(&mut ctx, &args, file) {
+            eprintln!("{}: {}", file.display(), e);
+            failed = true;
+        }
+    }
+    if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+fn transcribe_file(
+    ctx: &mut WhisperContext,
+    args: &Args,
+    file: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let audio = read_wav(file)?;
+
+    let strategy = match args.beam_size {
+        Some(beam_size) => SamplingStrategy::BeamSearch {
+            beam_size,
+            patience: -1.0,
+        },
+        None => SamplingStrategy::Greedy { best_of: 1 },
+    };
+    let params = FullParamsBuilder::new(strategy)
+        .n_threads(args.threads)
+        .language(Some(&args.language))
+        .task(if args.translate {
+            Task::Translate
+        } else {
+            Task::Transcribe
+        })
+        .token_timestamps(args.format.word_timed())
+        .console_output(ConsoleOutput::None)
+        .build()?;
+
+    ctx.full(params, &audio)?;
+    let transcript = ctx.collect_transcript()?;
+    let output = render(&transcript, args.format)?;
+
+    if args.output_files {
+        let path = file.with_extension(args.format.extension());
+        File::create(&path)?.write_all(output.as_bytes())?;
+        eprintln!("{}: written to {}", file.display(), path.display());
+    } else {
+        print!("{}", output);
+    }
+    Ok(())
+}
+
+fn render(transcript: &Transcript, format: OutputFormat) -> Result<String, serde_json::Error> {
+    Ok(match format {
+        OutputFormat::Txt => {
+            let mut txt = transcript.text().trim().to_string();
+            txt.push('\n');
+            txt
+        }
+        OutputFormat::Srt => transcript.to_srt(),
+        OutputFormat::Vtt => transcript.to_vtt(),
+        OutputFormat::Lrc => transcript.to_lrc(),
+        OutputFormat::Ttml => transcript.to_ttml(),
+        OutputFormat::Ass => transcript.to_ass(),
+        OutputFormat::Csv => transcript.to_csv(),
+        OutputFormat::Tsv => transcript.to_tsv(),
+        OutputFormat::Json => serde_json::to_string_pretty(transcript)? + "\n",
+    })
+}
+
+fn read_wav(path: &Path) -> Result<Vec<f32>, Box<dyn std::error::Error>> {
+    let mut reader = hound::WavReader::open(path)?;
+    let spec = reader.spec();
+    if spec.sample_rate != whisper_rs_sys::WHISPER_SAMPLE_RATE {
+        return Err(format!(
+            "sample rate must be 16 kHz, not {} Hz (try `ffmpeg -i input -ar 16000 output.wav`)",
+            spec.sample_rate
+        )
+        .into());
+    }
+
+    let audio = match spec.sample_format {
+        hound::SampleFormat::Float => reader.samples::<f32>().collect::<Result<Vec<_>, _>>()?,
+        hound::SampleFormat::Int if spec.bits_per_sample == 16 => {
+            let samples = reader.samples::<i16>().collect::<Result<Vec<_>, _>>()?;
+            whisper_rs::convert_integer_to_float_audio(&samples)
+        }
+        hound::SampleFormat::Int => {
+            return Err(format!(
+                "only 16 bit integer samples are supported, not {} bit",
+                spec.bits_per_sample
+            )
+            .into())
+        }
+    };
+
+    match spec.channels {
+        1 => Ok(audio),
+        2 => Ok(whisper_rs::convert_stereo_to_mono_audio(&audio)?),
+        n => Err(format!(
+            "only mono and stereo audio is supported, not {} channels",
+            n
+        )
+        .into()),
+    }
+}
@@ -0,0 +1,431 @@
+//! Realtime audio capture from a microphone, converted to the format whisper.cpp expects.
+//!
+//! Only available with the `capture` feature.
+//!
+//! ```no_run
+//! use whisper_rs::capture::MicrophoneSource;
+//! # use whisper_rs::{FullParams, SamplingStrategy, WhisperContext};
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! # let mut ctx = WhisperContext::new("model.bin")?;
+//! let mic = MicrophoneSource::new()?;
+//! loop {
+//!     std::thread::sleep(std::time::Duration::from_secs(5));
+//!     let audio = mic.take_samples();
+//!     let params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+//!     ctx.full(params, &audio)?;
+//!     print!("{}", ctx.collect_transcript()?.text());
+//! }
+//! # }
+//! ```
+
+use crate::AudioRingBuffer;
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{Device, FromSample, Sample, SampleFormat, SizedSample, Stream, StreamConfig};
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Sample rate of the audio given to whisper.cpp.
+const SAMPLE_RATE: u32 = whisper_rs_sys::WHISPER_SAMPLE_RATE;
+
+/// Seconds of audio buffered by [MicrophoneSource::new].
+const DEFAULT_BUFFER_SECONDS: usize = 30;
+
+/// An error that occurred while opening or running an audio input device.
+#[derive(Debug)]
+pub enum CaptureError {
+    /// No input device with the requested name, or no default input device.
+    NoDevice,
+    /// The devices could not be listed.
+    Devices(cpal::DevicesError),
+    /// The name of a device could not be read.
+    DeviceName(cpal::DeviceNameError),
+    /// The device did not report a configuration to record with.
+    Config(cpal::DefaultStreamConfigError),
+    /// The device records in a sample format that is not supported.
+    UnsupportedSampleFormat(SampleFormat),
+    /// The input stream could not be opened.
+    BuildStream(cpal::BuildStreamError),
+    /// The input stream could not be started.
+    PlayStream(cpal::PlayStreamError),
+    /// The input stream could not be paused.
+    PauseStream(cpal::PauseStreamError),
+    /// The input stream failed while recording.
+    Stream(cpal::StreamError),
+}
+
+impl fmt::Display for CaptureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoDevice => write!(f, "no audio input device found"),
+            Self::Devices(e) => write!(f, "unable to list audio input devices: {}", e),
+            Self::DeviceName(e) => write!(f, "unable to read audio input device name: {}", e),
+            Self::Config(e) => write!(f, "unable to configure audio input device: {}", e),
+            Self::UnsupportedSampleFormat(format) => {
+                write!(f, "unsupported audio input sample format: {}", format)
+            }
+            Self::BuildStream(e) => write!(f, "unable to open audio input stream: {}", e),
+            Self::PlayStream(e) => write!(f, "unable to start audio input stream: {}", e),
+            Self::PauseStream(e) => write!(f, "unable to pause audio input stream: {}", e),
+            Self::Stream(e) => write!(f, "audio input stream failed: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for CaptureError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::NoDevice | Self::UnsupportedSampleFormat(_) => None,
+            Self::Devices(e) => Some(e),
+            Self::DeviceName(e) => Some(e),
+            Self::Config(e) => Some(e),
+            Self::BuildStream(e) => Some(e),
+            Self::PlayStream(e) => Some(e),
+            Self::PauseStream(e) => Some(e),
+            Self::Stream(e) => Some(e),
+        }
+    }
+}
+
+/// List the names of the audio input devices of the default host.
+///
+/// # Returns
+/// Ok(Vec<String>) on success, Err(CaptureError) on failure.
+pub fn input_devices() -> Result<Vec<String>, CaptureError> {
+    cpal::default_host()
+        .input_devices()
+        .map_err(CaptureError::Devices)?
+        .map(|device| device.name().map_err(CaptureError::DeviceName))
+        .collect()
+}
+
+/// Records from an audio input device into a ring buffer of 16 kHz mono samples,
+/// ready to be passed to [WhisperContext::full](crate::WhisperContext::full).
+///
+/// Recording starts as soon as this is created, and stops when it is dropped.
+/// Once the buffer is full, the oldest samples are discarded.
+pub struct MicrophoneSource {
+    stream: Stream,
+    shared: Arc<Mutex<Shared>>,
+}
+
+struct Shared {
+    samples: AudioRingBuffer,
+    error: Option<cpal::StreamError>,
+}
+
+impl fmt::Debug for MicrophoneSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MicrophoneSource")
+            .field("available", &self.available())
+            .finish_non_exhaustive()
+    }
+}
+
+impl MicrophoneSource {
+    /// Start recording from the default input device, buffering up to 30 seconds of audio.
+    ///
+    /// # Returns
+    /// Ok(Self) on success, Err(CaptureError) on failure.
+    pub fn new() -> Result<Self, CaptureError> {
+        Self::open(None, DEFAULT_BUFFER_SECONDS)
+    }
+
+    /// Start recording from an input device.
+    ///
+    /// # Arguments
+    /// * device_name: Name of the device, as returned by [input_devices]. None for the default device.
+    /// * buffer_seconds: How many seconds of audio to buffer. Must be at least 1.
+    ///
+    /// # Returns
+    /// Ok(Self) on success, Err(CaptureError) on failure.
+    pub fn open(device_name: Option<&str>, buffer_seconds: usize) -> Result<Self, CaptureError> {
+        let host = cpal::default_host();
+        let device = match device_name {
+            None => host.default_input_device(),
+            Some(name) => host
+                .input_devices()
+                .map_err(CaptureError::Devices)?
+                .find(|device| device.name().is_ok_and(|n| n == name)),
+        }
+        .ok_or(CaptureError::NoDevice)?;
+
+        let supported = device
+            .default_input_config()
+            .map_err(CaptureError::Config)?;
+        let sample_format = supported.sample_format();
+        let config = supported.config();
+
+        let shared = Arc::new(Mutex::new(Shared {
+            samples: AudioRingBuffer::with_capacity(buffer_seconds.max(1) * SAMPLE_RATE as usize),
+            error: None,
+        }));
+
+        let stream = match sample_format {
+            SampleFormat::F32 => build_stream::<f32>(&device, &config, &shared),
+            SampleFormat::I16 => build_stream::<i16>(&device, &config, &shared),
+            SampleFormat::U16 => build_stream::<u16>(&device, &config, &shared),
+            SampleFormat::I32 => build_stream::<i32>(&device, &config, &shared),
+            format => return Err(CaptureError::UnsupportedSampleFormat(format)),
+        }?;
+        stream.play().map_err(CaptureError::PlayStream)?;
+
+        Ok(Self { stream, shared })
+    }
+
+    /// Number of samples currently buffered.
+    pub fn available(&self) -> usize {
+        self.lock().samples.len()
+    }
+
+    /// Take all buffered samples, leaving the buffer empty.
+    pub fn take_samples(&self) -> Vec<f32> {
+        self.lock().samples.take_all()
+    }
+
+    /// Copy the most recent `n` buffered samples, or fewer if not as many are buffered, leaving the buffer untouched.
+    ///
+    /// This is useful for transcribing a sliding window of the audio.
+    pub fn latest_samples(&self, n: usize) -> Vec<f32> {
+        self.lock().samples.latest(n)
+    }
+
+    /// Take a window of the oldest buffered samples, see [AudioRingBuffer::drain_window].
+    pub fn drain_window(&self, window_len: usize, keep_len: usize) -> Option<Vec<f32>> {
+        self.lock().samples.drain_window(window_len, keep_len)
+    }
+
+    /// Discard all buffered samples.
+    pub fn clear(&self) {
+        self.lock().samples.clear();
+    }
+
+    /// Take the error the stream failed with while recording, if any.
+    pub fn take_error(&self) -> Option<CaptureError> {
+        self.lock().error.take().map(CaptureError::Stream)
+    }
+
+    /// Pause recording.
+    pub fn pause(&self) -> Result<(), CaptureError> {
+        self.stream.pause().map_err(CaptureError::PauseStream)
+    }
+
+    /// Resume recording after [MicrophoneSource::pause].
+    pub fn resume(&self) -> Result<(), CaptureError> {
+        self.stream.play().map_err(CaptureError::PlayStream)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Shared> {
+        // the buffer is never left in an inconsistent state, so a poisoned lock is fine to reuse
+        self.shared.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn build_stream<T>(
+    device: &Device,
+    config: &StreamConfig,
+    shared: &Arc<Mutex<Shared>>,
+) -> Result<Stream, CaptureError>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    let channels = config.channels.max(1) as usize;
+    let mut resampler = Resampler::new(config.sample_rate.0, SAMPLE_RATE);
+    let mut mono = Vec::new();
+    let mut resampled = Vec::new();
+
+    let data_shared = Arc::clone(shared);
+    let error_shared = Arc::clone(shared);
+    device
+        .build_input_stream(
+            config,
+            move |data: &[T], _: &cpal::InputCallbackInfo| {
+                mono.clear();
+                mono.extend(data.chunks(channels).map(|frame| {
+                    frame.iter().map(|&s| f32::from_sample(s)).sum::<f32>() / frame.len() as f32
+                }));
+                resampled.clear();
+                resampler.process(&mono, &mut resampled);
+
+                data_shared
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .samples
+                    .push(&resampled);
+            },
+            move |e| {
+                error_shared.lock().unwrap_or_else(|e| e.into_inner()).error = Some(e);
+            },
+            None,
+        )
+        .map_err(CaptureError::BuildStream)
+}
+
+/// Streaming linear interpolation resampler for mono audio.
+///
+/// When downsampling, the input is low-pass filtered first, so that what is above the new Nyquist frequency
+/// doesn't fold back into the speech band.
+struct Resampler {
+    /// input samples per output sample
+    step: f64,
+    /// position of the next output sample, relative to the start of the next input chunk
+    pos: f64,
+    /// last sample of the previous input chunk, at position -1
+    prev: f32,
+    /// anti-aliasing filter, empty unless downsampling
+    filter: Vec<Biquad>,
+    /// scratch buffer for the filtered input, kept to reuse its allocation
+    filtered: Vec<f32>,
+}
+
+impl Resampler {
+    fn new(from_rate: u32, to_rate: u32) -> Self {
+        let filter = if from_rate > to_rate {
+            // 8th order Butterworth, made of 4 biquads, with its cutoff just below the new Nyquist frequency
+            const ORDER: usize = 8;
+            let cutoff = 0.45 * to_rate as f64;
+            (1..=ORDER / 2)
+                .map(|k| {
+                    let angle = std::f64::consts::PI * (2 * k - 1) as f64 / (2 * ORDER) as f64;
+                    Biquad::low_pass(cutoff / from_rate as f64, 1.0 / (2.0 * angle.sin()))
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+        Self {
+            step: from_rate as f64 / to_rate as f64,
+            pos: 0.0,
+            prev: 0.0,
+            filter,
+            filtered: Vec::new(),
+        }
+    }
+
+    fn process(&mut self, input: &[f32], output: &mut Vec<f32>) {
+        let input = if self.filter.is_empty() {
+            input
+        } else {
+            self.filtered.clear();
+            self.filtered.extend(input.iter().map(|&sample| {
+                self.filter
+                    .iter_mut()
+                    .fold(sample, |sample, biquad| biquad.process(sample))
+            }));
+            &self.filtered
+        };
+        let Some(&last) = input.last() else {
+            return;
+        };
+        let sample = |i: isize| if i < 0 { self.prev } else { input[i as usize] };
+        let end = (input.len() - 1) as f64;
+        while self.pos <= end {
+            let i = self.pos.floor();
+            let frac = (self.pos - i) as f32;
+            let a = sample(i as isize);
+            let b = if self.pos < end {
+                sample(i as isize + 1)
+            } else {
+                a
+            };
+            output.push(a + (b - a) * frac);
+            self.pos += self.step;
+        }
+        self.pos -= input.len() as f64;
+        self.prev = last;
+    }
+}
+
+/// A second order IIR filter section, in transposed direct form II.
+struct Biquad {
+    b0: f64,
+    b1: f64,
+    b2: f64,
+    a1: f64,
+    a2: f64,
+    z1: f64,
+    z2: f64,
+}
+
+impl Biquad {
+    /// Low-pass filter from the Audio EQ Cookbook.
+    ///
+    /// # Arguments
+    /// * cutoff: Cutoff frequency, as a fraction of the sample rate.
+    /// * q: Quality factor.
+    fn low_pass(cutoff: f64, q: f64) -> Self {
+        let w0 = 2.0 * std::f64::consts::PI * cutoff;
+        let (sin, cos) = w0.sin_cos();
+        let alpha = sin / (2.0 * q);
+        let a0 = 1.0 + alpha;
+        Self {
+            b0: (1.0 - cos) / 2.0 / a0,
+            b1: (1.0 - cos) / a0,
+            b2: (1.0 - cos) / 2.0 / a0,
+            a1: -2.0 * cos / a0,
+            a2: (1.0 - alpha) / a0,
+            z1: 0.0,
+            z2: 0.0,
+        }
+    }
+
+    fn process(&mut self, sample: f32) -> f32 {
+        let x = sample as f64;
+        let y = self.b0 * x + self.z1;
+        self.z1 = self.b1 * x - self.a1 * y + self.z2;
+        self.z2 = self.b2 * x - self.a2 * y;
+        y as f32
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn assert_resampler_keeps_rate() {
+        let input: Vec<f32> = (0..480).map(|i| i as f32).collect();
+        let mut output = Vec::new();
+        let mut resampler = Resampler::new(16000, 16000);
+        resampler.process(&input, &mut output);
+        assert_eq!(output, input);
+    }
+
+    #[test]
+    fn assert_resampler_downsamples_across_chunks() {
+        let input: Vec<f32> = (0..4800).map(|i| i as f32).collect();
+        let mut output = Vec::new();
+        let mut resampler = Resampler::new(48000, 16000);
+        for chunk in input.chunks(131) {
+            resampler.process(chunk, &mut output);
+        }
+        assert_eq!(output.len(), 1600);
+
+        let mut whole = Vec::new();
+        Resampler::new(48000, 16000).process(&input, &mut whole);
+        assert_eq!(output.len(), whole.len());
+        for (chunked, whole) in output.iter().zip(&whole) {
+            assert!((chunked - whole).abs() < 1e-3);
+        }
+    }
+
+    /// RMS level of a sine tone at `frequency` after resampling 48 kHz to 16 kHz, ignoring the filter's transient.
+    fn resampled_level(frequency: f64) -> f32 {
+        let input: Vec<f32> = (0..48000)
+            .map(|i| (2.0 * std::f64::consts::PI * frequency * i as f64 / 48000.0).sin() as f32)
+            .collect();
+        let mut output = Vec::new();
+        Resampler::new(48000, 16000).process(&input, &mut output);
+        let settled = &output[1600..];
+        (settled.iter().map(|s| s * s).sum::<f32>() / settled.len() as f32).sqrt()
+    }
+
+    #[test]
+    fn assert_resampler_filters_aliases() {
+        // passes speech
+        assert!((resampled_level(1000.0) - 0.5f32.sqrt()).abs() < 0.01);
+        // above the new Nyquist frequency of 8 kHz, these would fold back to 4 kHz and 0 Hz
+        assert!(resampled_level(12000.0) < 0.01);
+        assert!(resampled_level(16000.0) < 0.001);
+    }
+}
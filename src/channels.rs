@@ -0,0 +1,254 @@
+//! Transcribing every channel of a multi-channel recording on its own,
+//! e.g. call recordings with each party on a separate channel.
+//!
+//! ```no_run
+//! use whisper_rs::{channels, FullParams, SamplingStrategy, WhisperContext};
+//!
+//! let mut ctx = WhisperContext::new("model.bin").expect("failed to load model");
+//! let stereo: Vec<f32> = vec![0.0; 2 * 16000];
+//! let transcripts = channels::transcribe_channels(&mut ctx, &stereo, 2, || {
+//!     FullParams::new(SamplingStrategy::Greedy { best_of: 1 })
+//! })
+//! .expect("failed to transcribe");
+//! for (channel, transcript) in transcripts.iter().enumerate() {
+//!     println!("channel {}: {}", channel, transcript.text());
+//! }
+//!
+//! // or as a single conversation, with one speaker per channel
+//! let conversation = channels::Conversation::interleave(transcripts);
+//! print!("{}", conversation.text(&["Agent", "Customer"]));
+//! ```
+
+use crate::{
+    batch, FullParams, Segment, Transcript, WhisperContext, WhisperContextPool, WhisperError,
+};
+
+/// Split interleaved PCM audio into one buffer per channel.
+///
+/// # Arguments
+/// * interleaved: PCM audio data, with the samples of all channels interleaved.
+/// * n_channels: Number of channels.
+///
+/// # Returns
+/// Ok(Vec) with the audio of every channel on success.
+/// Err(WhisperError::InvalidParameter) if there are no channels, or the samples don't divide evenly between them.
+pub fn split_channels(
+    interleaved: &[f32],
+    n_channels: usize,
+) -> Result<Vec<Vec<f32>>, WhisperError> {
+    if n_channels == 0 {
+        return Err(WhisperError::InvalidParameter { name: "n_channels" });
+    }
+    let frames = interleaved.chunks_exact(n_channels);
+    if !frames.remainder().is_empty() {
+        return Err(WhisperError::InvalidParameter { name: "n_channels" });
+    }
+    let mut channels = vec![Vec::with_capacity(frames.len()); n_channels];
+    for frame in frames {
+        for (channel, &sample) in channels.iter_mut().zip(frame) {
+            channel.push(sample);
+        }
+    }
+    Ok(channels)
+}
+
+/// Transcribe every channel of interleaved PCM audio separately, one after the other.
+///
+/// The context is [reset](WhisperContext::reset) before every channel,
+/// so the text of one channel is not used as the prompt of the next.
+///
+/// # Arguments
+/// * ctx: The context to run the model with.
+/// * interleaved: PCM audio data, with the samples of all channels interleaved.
+/// * n_channels: Number of channels.
+/// * make_params: Called to create the parameters for each channel.
+///
+/// # Returns
+/// Ok(Vec) with the transcript of every channel, in channel order, on success.
+/// Err(WhisperError) if splitting the audio or transcribing any channel failed.
+pub fn transcribe_channels(
+    ctx: &mut WhisperContext,
+    interleaved: &[f32],
+    n_channels: usize,
+    mut make_params: impl FnMut() -> FullParams,
+) -> Result<Vec<Transcript>, WhisperError> {
+    split_channels(interleaved, n_channels)?
+        .iter()
+        .map(|channel| {
+            ctx.reset();
+            ctx.full(make_params(), channel)?;
+            ctx.collect_transcript()
+        })
+        .collect()
+}
+
+/// Transcribe every channel of interleaved PCM audio separately, all at the same time.
+///
+/// Like [transcribe_channels], but every channel is transcribed with its own context from the pool,
+/// using [batch::transcribe_all].
+///
+/// # Arguments
+/// * pool: The contexts to run the model with. Channels beyond its size wait for a context to become free.
+/// * interleaved: PCM audio data, with the samples of all channels interleaved.
+/// * n_channels: Number of channels.
+/// * make_params: Called to create the parameters for each channel.
+///
+/// # Returns
+/// Ok(Vec) with the transcript of every channel, in channel order, on success.
+/// Err(WhisperError) if splitting the audio or transcribing any channel failed.
+pub fn transcribe_channels_parallel(
+    pool: &WhisperContextPool,
+    interleaved: &[f32],
+    n_channels: usize,
+    make_params: impl Fn() -> FullParams + Sync,
+) -> Result<Vec<Transcript>, WhisperError> {
+    let channels = split_channels(interleaved, n_channels)?;
+    batch::transcribe_all(pool, &channels, n_channels, make_params)
+        .into_iter()
+        .collect()
+}
+
+/// A segment of a [Conversation], along with who said it.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpeakerSegment {
+    /// Index of the speaker, which is the index of the channel or transcript the segment came from.
+    pub speaker: usize,
+    /// The segment itself.
+    pub segment: Segment,
+}
+
+/// The transcripts of several speakers, merged into a single time-ordered transcript.
+///
+/// Create this with [Conversation::interleave], e.g. from the result of [transcribe_channels].
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Conversation {
+    /// Segments of all speakers, ordered by start time.
+    pub segments: Vec<SpeakerSegment>,
+}
+
+impl Conversation {
+    /// Merge the transcripts of several speakers, which must share the same timeline.
+    ///
+    /// Segments are ordered by the time they start at. When speakers talk over each other,
+    /// each segment is placed where its speaker started talking, even if someone else was still speaking.
+    /// Segments starting at the same time are ordered by the time they end at, then by speaker.
+    ///
+    /// # Arguments
+    /// * transcripts: The transcript of every speaker. The index of a transcript is used as its speaker index.
+    pub fn interleave(transcripts: impl IntoIterator<Item = Transcript>) -> Self {
+        let mut segments: Vec<SpeakerSegment> = transcripts
+            .into_iter()
+            .enumerate()
+            .flat_map(|(speaker, transcript)| {
+                transcript
+                    .segments
+                    .into_iter()
+                    .map(move |segment| SpeakerSegment { speaker, segment })
+            })
+            .collect();
+        segments.sort_by_key(|s| (s.segment.t0, s.segment.t1, s.speaker));
+        Self { segments }
+    }
+
+    /// Render the conversation as text, with a line for every turn prefixed by the name of its speaker.
+    ///
+    /// Consecutive segments of the same speaker are joined into a single turn.
+    ///
+    /// # Arguments
+    /// * labels: Names of the speakers, by speaker index. Speakers without a name are called "Speaker 1", "Speaker 2", ...
+    pub fn text(&self, labels: &[&str]) -> String {
+        let mut text = String::new();
+        let mut last_speaker = None;
+        for SpeakerSegment { speaker, segment } in &self.segments {
+            if last_speaker != Some(*speaker) {
+                if last_speaker.is_some() {
+                    text.push('\n');
+                }
+                text.push_str(&label(labels, *speaker));
+                text.push(':');
+                last_speaker = Some(*speaker);
+            }
+            text.push(' ');
+            text.push_str(segment.text.trim());
+        }
+        if last_speaker.is_some() {
+            text.push('\n');
+        }
+        text
+    }
+
+    /// Render the conversation as WebVTT subtitles, with one cue per segment
+    /// and the name of its speaker in a voice tag.
+    ///
+    /// # Arguments
+    /// * labels: Names of the speakers, as for [Conversation::text].
+    pub fn to_vtt(&self, labels: &[&str]) -> String {
+        let mut vtt = String::from("WEBVTT\n\n");
+        for SpeakerSegment { speaker, segment } in &self.segments {
+            vtt.push_str(&format!(
+                "{} --> {}\n<v {}>{}\n\n",
+                segment.start().to_vtt(),
+                segment.end().to_vtt(),
+                label(labels, *speaker),
+                segment.text.trim()
+            ));
+        }
+        vtt
+    }
+}
+
+fn label(labels: &[&str], speaker: usize) -> String {
+    match labels.get(speaker) {
+        Some(label) => label.to_string(),
+        None => format!("Speaker {}", speaker + 1),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn assert_split_channels() {
+        let channels = split_channels(&[1.0, -1.0, 2.0, -2.0, 3.0, -3.0], 2).unwrap();
+        assert_eq!(channels, [vec![1.0, 2.0, 3.0], vec![-1.0, -2.0, -3.0]]);
+        assert!(split_channels(&[1.0, 2.0, 3.0], 2).is_err());
+        assert!(split_channels(&[], 0).is_err());
+    }
+
+    #[test]
+    fn assert_interleaves_transcripts() {
+        let transcript = |segments: &[(i64, i64, &str)]| Transcript {
+            segments: segments
+                .iter()
+                .map(|&(t0, t1, text)| Segment {
+                    text: text.to_string(),
+                    t0,
+                    t1,
+                    tokens: vec![],
+                })
+                .collect(),
+            ..Default::default()
+        };
+        let agent = transcript(&[(0, 200, " Hello, how can I help?"), (500, 700, " Sure.")]);
+        let customer = transcript(&[
+            (150, 450, " Hi, I have a question."),
+            (450, 480, " About my bill."),
+        ]);
+        let conversation = Conversation::interleave([agent, customer]);
+
+        let order: Vec<_> = conversation.segments.iter().map(|s| s.speaker).collect();
+        assert_eq!(order, [0, 1, 1, 0]);
+        assert_eq!(
+            conversation.text(&["Agent"]),
+            "Agent: Hello, how can I help?\n\
+            Speaker 2: Hi, I have a question. About my bill.\n\
+            Agent: Sure.\n"
+        );
+        assert!(conversation
+            .to_vtt(&["Agent", "Customer"])
+            .contains("00:00:01.500 --> 00:00:04.500\n<v Customer>Hi, I have a question.\n"));
+    }
+}
@@ -0,0 +1,93 @@
+use crate::{FullParams, Language, Task, Transcript, WhisperContext, WhisperError};
+use std::collections::VecDeque;
+
+/// Something that can turn audio into a [Transcript].
+///
+/// Write pipelines against this trait instead of [WhisperContext] directly,
+/// so they can be unit tested with a [MockEngine] instead of a real model.
+pub trait TranscriptionEngine {
+    /// Run the model on the audio and collect the results.
+    ///
+    /// # Arguments
+    /// * params: [crate::FullParams] struct.
+    /// * pcm: PCM audio data.
+    ///
+    /// # Returns
+    /// Ok(Transcript) on success, Err(WhisperError) on failure.
+    fn transcribe(&mut self, params: FullParams, pcm: &[f32]) -> Result<Transcript, WhisperError>;
+}
+
+impl TranscriptionEngine for WhisperContext {
+    fn transcribe(&mut self, params: FullParams, pcm: &[f32]) -> Result<Transcript, WhisperError> {
+        self.full(params, pcm)?;
+        self.collect_transcript()
+    }
+}
+
+impl<T: TranscriptionEngine + ?Sized> TranscriptionEngine for &mut T {
+    fn transcribe(&mut self, params: FullParams, pcm: &[f32]) -> Result<Transcript, WhisperError> {
+        (**self).transcribe(params, pcm)
+    }
+}
+
+/// A [TranscriptionEngine] that returns canned results instead of running a model.
+///
+/// Results are returned in the order they were added, and every request is recorded for later inspection.
+#[derive(Debug, Default)]
+pub struct MockEngine {
+    results: VecDeque<Result<Transcript, WhisperError>>,
+    requests: Vec<MockRequest>,
+}
+
+/// A request made to a [MockEngine].
+#[derive(Debug, Clone, PartialEq)]
+pub struct MockRequest {
+    /// The audio that was to be transcribed.
+    pub pcm: Vec<f32>,
+    /// The task that was requested.
+    pub task: Task,
+    /// The language that was requested, None for auto-detection.
+    pub language: Option<Language>,
+}
+
+impl MockEngine {
+    /// Create an engine that returns the given transcripts, in order.
+    pub fn new(transcripts: impl IntoIterator<Item = Transcript>) -> Self {
+        Self {
+            results: transcripts.into_iter().map(Ok).collect(),
+            requests: Vec::new(),
+        }
+    }
+
+    /// Queue another result, e.g. an error to check how a pipeline handles it.
+    pub fn push_result(&mut self, result: Result<Transcript, WhisperError>) {
+        self.results.push_back(result);
+    }
+
+    /// All requests made so far, oldest first.
+    pub fn requests(&self) -> &[MockRequest] {
+        &self.requests
+    }
+
+    /// Number of results that have not been returned yet.
+    pub fn remaining(&self) -> usize {
+        self.results.len()
+    }
+}
+
+impl TranscriptionEngine for MockEngine {
+    /// Return the next queued result.
+    ///
+    /// # Panics
+    /// Panics if there are no results left, as this means the code under test made more requests than expected.
+    fn transcribe(&mut self, params: FullParams, pcm: &[f32]) -> Result<Transcript, WhisperError> {
+        self.requests.push(MockRequest {
+            pcm: pcm.to_vec(),
+            task: params.task(),
+            language: params.language(),
+        });
+        self.results
+            .pop_front()
+            .expect("MockEngine ran out of results")
+    }
+}
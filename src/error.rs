@@ -1,4 +1,5 @@
 use std::ffi::{c_int, NulError};
+use std::fmt;
 use std::str::Utf8Error;
 
 /// Whisper tends to output errors to stderr, so if an error occurs, check stderr.
@@ -20,10 +21,28 @@ pub enum WhisperError {
     FailedToEncode,
     /// Failed to run the decoder
     FailedToDecode,
+    /// Failed to auto-detect the spoken language.
+    FailedToDetectLanguage,
     /// Invalid number of mel bands.
     InvalidMelBands,
+    /// The model expects a number of mel bands the bundled whisper.cpp can't compute, see [ModelHeader::check_compatible](crate::ModelHeader::check_compatible).
+    UnsupportedMelBands { model: c_int },
     /// Invalid thread count
     InvalidThreadCount,
+    /// Invalid values in the sampling strategy, see [SamplingStrategy::validate](crate::SamplingStrategy::validate).
+    InvalidSamplingStrategy,
+    /// The audio context size is negative or larger than the model's, see [WhisperContext::n_audio_ctx](crate::WhisperContext::n_audio_ctx).
+    InvalidAudioCtx,
+    /// whisper.cpp would skip the audio passed to [WhisperContext::full](crate::WhisperContext::full) without transcribing it:
+    /// it is shorter than 1 second, or [FullParams::set_duration_ms](crate::FullParams::set_duration_ms) is set to less than
+    /// 1 second (0.5 seconds with [FullParams::set_speed_up](crate::FullParams::set_speed_up)).
+    /// Like whisper.cpp, this ignores [FullParams::set_offset_ms](crate::FullParams::set_offset_ms).
+    AudioTooShort,
+    /// The audio has more samples than whisper.cpp can count, which is about 37 hours at 16 kHz.
+    AudioTooLong,
+    /// A callback passed to [WhisperContext::full_with_segment_callback](crate::WhisperContext::full_with_segment_callback)
+    /// panicked. The panic was caught, and the run was stopped.
+    CallbackPanicked,
     /// Invalid UTF-8 detected in a string from Whisper.
     InvalidUtf8 {
         error_len: Option<usize>,
@@ -33,12 +52,71 @@ pub enum WhisperError {
     NullByteInString { idx: usize },
     /// Whisper returned a null pointer.
     NullPointer,
-    /// Generic whisper error. Varies depending on the function.
+    /// Generic whisper error. Varies depending on the function, and contains the return code.
     GenericError(c_int),
     /// Whisper failed to convert the provided text into tokens.
     InvalidText,
+    /// The language is not known to whisper.cpp.
+    InvalidLanguage,
+    /// A parameter is out of range, see [FullParamsBuilder::build](crate::FullParamsBuilder::build).
+    InvalidParameter { name: &'static str },
+    /// A [WhisperContextPool](crate::WhisperContextPool) without any contexts was given to run the model with.
+    EmptyPool,
 }
 
+impl fmt::Display for WhisperError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use WhisperError::*;
+        match self {
+            InitError => write!(f, "failed to create a new whisper context"),
+            SpectrogramNotInitialized => write!(f, "the spectrogram has not been initialized"),
+            EncodeNotComplete => write!(f, "encode has not been called"),
+            DecodeNotComplete => write!(f, "decode has not been called"),
+            UnableToCalculateSpectrogram => write!(f, "failed to calculate the spectrogram"),
+            UnableToCalculateEvaluation => write!(f, "failed to evaluate the model"),
+            FailedToEncode => write!(f, "failed to run the encoder"),
+            FailedToDecode => write!(f, "failed to run the decoder"),
+            FailedToDetectLanguage => write!(f, "failed to auto-detect the language"),
+            InvalidMelBands => write!(f, "invalid number of mel bands"),
+            UnsupportedMelBands { model } => write!(
+                f,
+                "the model expects {} mel bands, but only {} are supported",
+                model,
+                whisper_rs_sys::WHISPER_N_MEL
+            ),
+            InvalidThreadCount => write!(f, "invalid thread count"),
+            InvalidSamplingStrategy => write!(f, "invalid sampling strategy"),
+            InvalidAudioCtx => write!(f, "invalid audio context size"),
+            AudioTooShort => write!(f, "the audio is too short to transcribe"),
+            AudioTooLong => write!(f, "the audio has too many samples"),
+            CallbackPanicked => write!(f, "a callback panicked"),
+            InvalidUtf8 {
+                error_len,
+                valid_up_to,
+            } => write!(
+                f,
+                "invalid UTF-8 in string from whisper after {} valid bytes{}",
+                valid_up_to,
+                match error_len {
+                    Some(len) => format!(" ({} invalid bytes)", len),
+                    None => " (unexpected end of input)".to_string(),
+                }
+            ),
+            NullByteInString { idx } => write!(f, "null byte in string at index {}", idx),
+            NullPointer => write!(f, "whisper returned a null pointer"),
+            GenericError(code) => write!(f, "whisper returned error code {}", code),
+            InvalidText => write!(f, "failed to convert the text into tokens"),
+            InvalidLanguage => write!(f, "unknown language"),
+            InvalidParameter { name } => write!(f, "invalid value for parameter {}", name),
+            EmptyPool => write!(f, "the context pool has no contexts"),
+        }
+    }
+}
+
+// none of the variants wrap another error:
+// the UTF-8 and null byte errors are flattened into their fields so that WhisperError stays Copy
+impl std::error::Error for WhisperError {}
+
 impl From<Utf8Error> for WhisperError {
     fn from(e: Utf8Error) -> Self {
         Self::InvalidUtf8 {
@@ -0,0 +1,291 @@
+//! Post-processing that removes segments the model most likely made up.
+//!
+//! Whisper sometimes gets stuck repeating the same few words, or produces text with a very low probability,
+//! in particular on silence and music. Such segments can be detected after the fact
+//! from the repetition in their text and the statistics of their tokens.
+//!
+//! ```
+//! use whisper_rs::hallucination::{filter_hallucinations, FilterConfig};
+//! # let transcript = whisper_rs::Transcript::default();
+//!
+//! let filtered = filter_hallucinations(transcript, &FilterConfig::default());
+//! for removed in &filtered.removed {
+//!     eprintln!("dropped segment {}: {:?}", removed.index, removed.reason);
+//! }
+//! let text = filtered.kept.text();
+//! ```
+
+use crate::{Segment, Transcript};
+
+/// Settings for [filter_hallucinations]. Set a threshold to None to disable its check.
+///
+/// The bundled whisper.cpp does not report the probability that a segment contains no speech,
+/// so unlike OpenAI's reference implementation there is no threshold for it.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct FilterConfig {
+    /// Segments with a higher [Segment::compression_ratio] are removed.
+    /// Only available with the `compression-ratio` feature.
+    ///
+    /// Defaults to 2.4, the threshold whisper.cpp and OpenAI's reference implementation use for falling back.
+    #[cfg(feature = "compression-ratio")]
+    pub max_compression_ratio: Option<f32>,
+    /// Segments with a lower [Segment::avg_logprob] are removed.
+    ///
+    /// Defaults to -1.0, the threshold whisper.cpp and OpenAI's reference implementation use for falling back.
+    pub min_avg_logprob: Option<f32>,
+    /// Longest sequence of words that is checked for being repeated over and over.
+    ///
+    /// Defaults to 4.
+    pub max_ngram_len: usize,
+    /// Segments in which a sequence of up to [FilterConfig::max_ngram_len] words
+    /// is repeated more often than this in a row are removed.
+    ///
+    /// Defaults to 4, so "no no no no" is kept but "no no no no no" is not.
+    pub max_ngram_repeats: Option<usize>,
+    /// Remove segments that only describe sounds other than speech, such as "[BLANK_AUDIO]", "(music)" or "♪♪".
+    ///
+    /// The bundled whisper.cpp can't suppress the tokens these are made of while decoding,
+    /// as it has no `suppress_non_speech_tokens` parameter, so they can only be removed afterwards.
+    ///
+    /// Defaults to true.
+    pub remove_non_speech: bool,
+}
+
+impl Default for FilterConfig {
+    fn default() -> Self {
+        Self {
+            #[cfg(feature = "compression-ratio")]
+            max_compression_ratio: Some(2.4),
+            min_avg_logprob: Some(-1.0),
+            max_ngram_len: 4,
+            max_ngram_repeats: Some(4),
+            remove_non_speech: true,
+        }
+    }
+}
+
+impl FilterConfig {
+    /// Check a single segment.
+    ///
+    /// # Returns
+    /// Why the segment should be removed, None if it passes all checks.
+    pub fn check(&self, segment: &Segment) -> Option<FilterReason> {
+        if self.remove_non_speech && is_non_speech(&segment.text) {
+            return Some(FilterReason::NonSpeech);
+        }
+        if let Some(repeats) = self.max_ngram_repeats {
+            if let Some((ngram, count)) = longest_repetition(&segment.text, self.max_ngram_len) {
+                if count > repeats {
+                    return Some(FilterReason::Repetition { ngram, count });
+                }
+            }
+        }
+        #[cfg(feature = "compression-ratio")]
+        if let Some(max) = self.max_compression_ratio {
+            let ratio = segment.compression_ratio();
+            if ratio > max {
+                return Some(FilterReason::CompressionRatio(ratio));
+            }
+        }
+        if let (Some(min), Some(logprob)) = (self.min_avg_logprob, segment.avg_logprob()) {
+            if logprob < min {
+                return Some(FilterReason::AvgLogprob(logprob));
+            }
+        }
+        None
+    }
+}
+
+/// Why [filter_hallucinations] removed a segment.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FilterReason {
+    /// A sequence of words was repeated `count` times in a row.
+    Repetition { ngram: String, count: usize },
+    /// The text compressed too well, with the given ratio.
+    #[cfg(feature = "compression-ratio")]
+    CompressionRatio(f32),
+    /// The tokens were too unlikely, with the given mean log probability.
+    AvgLogprob(f32),
+    /// The text only describes sounds other than speech.
+    NonSpeech,
+}
+
+/// A segment removed by [filter_hallucinations].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RemovedSegment {
+    /// Index of the segment in the original transcript.
+    pub index: usize,
+    /// The segment itself.
+    pub segment: Segment,
+    /// Why it was removed.
+    pub reason: FilterReason,
+}
+
+/// The result of [filter_hallucinations].
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FilteredTranscript {
+    /// The transcript without the removed segments.
+    pub kept: Transcript,
+    /// The removed segments, in their original order.
+    pub removed: Vec<RemovedSegment>,
+}
+
+/// Remove segments that look like hallucinations from a transcript.
+///
+/// # Arguments
+/// * transcript: The transcript to filter.
+/// * config: Thresholds to use.
+///
+/// # Returns
+/// The remaining transcript, and the removed segments with the reason they were removed.
+pub fn filter_hallucinations(transcript: Transcript, config: &FilterConfig) -> FilteredTranscript {
+    let Transcript {
+        segments,
+        task,
+        language,
+        duration,
+    } = transcript;
+    let mut kept = Vec::with_capacity(segments.len());
+    let mut removed = Vec::new();
+    for (index, segment) in segments.into_iter().enumerate() {
+        match config.check(&segment) {
+            Some(reason) => removed.push(RemovedSegment {
+                index,
+                segment,
+                reason,
+            }),
+            None => kept.push(segment),
+        }
+    }
+    FilteredTranscript {
+        kept: Transcript {
+            segments: kept,
+            task,
+            language,
+            duration,
+        },
+        removed,
+    }
+}
+
+/// Does the text only consist of annotations such as "[BLANK_AUDIO]", "(music)" or "♪", and punctuation?
+fn is_non_speech(text: &str) -> bool {
+    let mut depth = 0usize;
+    let mut in_asterisks = false;
+    let mut has_annotation = false;
+    for c in text.chars() {
+        match c {
+            '[' | '(' => {
+                depth += 1;
+                has_annotation = true;
+            }
+            ']' | ')' => depth = depth.saturating_sub(1),
+            // e.g. *coughs*, which opens and closes with the same character
+            '*' => {
+                in_asterisks = !in_asterisks;
+                has_annotation = true;
+            }
+            '♪' | '♫' | '♬' | '♩' => has_annotation = true,
+            _ if depth == 0 && !in_asterisks && c.is_alphanumeric() => return false,
+            _ => {}
+        }
+    }
+    has_annotation
+}
+
+/// Find the sequence of up to `max_len` words that is repeated most often in a row, ignoring case and punctuation.
+///
+/// # Returns
+/// The sequence and how often it is repeated, None if the text has no words.
+fn longest_repetition(text: &str, max_len: usize) -> Option<(String, usize)> {
+    let words: Vec<String> = text
+        .split_whitespace()
+        .map(|word| {
+            word.chars()
+                .filter(|c| c.is_alphanumeric())
+                .flat_map(char::to_lowercase)
+                .collect::<String>()
+        })
+        .filter(|word| !word.is_empty())
+        .collect();
+
+    let mut best: Option<(&[String], usize)> = None;
+    for len in 1..=max_len.min(words.len()) {
+        for start in 0..=words.len() - len {
+            let ngram = &words[start..start + len];
+            let count = words[start..]
+                .chunks_exact(len)
+                .take_while(|chunk| *chunk == ngram)
+                .count();
+            if count > best.map_or(0, |(_, most)| most) {
+                best = Some((ngram, count));
+            }
+        }
+    }
+    best.map(|(ngram, count)| (ngram.join(" "), count))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn segment(text: &str) -> Segment {
+        Segment {
+            text: text.to_string(),
+            t0: 0,
+            t1: 100,
+            tokens: vec![],
+        }
+    }
+
+    #[test]
+    fn assert_repetition() {
+        assert_eq!(
+            longest_repetition(" Thank you. Thank you, thank you! Bye.", 4),
+            Some(("thank you".to_string(), 3))
+        );
+        assert_eq!(longest_repetition(" ...", 4), None);
+    }
+
+    #[test]
+    fn assert_non_speech() {
+        assert!(is_non_speech(" [BLANK_AUDIO]"));
+        assert!(is_non_speech(" (upbeat music) ♪♪"));
+        assert!(is_non_speech(" *coughs*"));
+        assert!(!is_non_speech(" [laughs] That's funny."));
+        assert!(!is_non_speech(" Hello."));
+        assert!(!is_non_speech(""));
+    }
+
+    #[test]
+    fn assert_filters_transcript() {
+        let transcript = Transcript {
+            segments: vec![
+                segment(" Hello there."),
+                segment(&" I'm sorry.".repeat(6)),
+                segment(" No no no no."),
+            ],
+            ..Default::default()
+        };
+        let filtered = filter_hallucinations(transcript, &FilterConfig::default());
+
+        assert_eq!(filtered.kept.segments.len(), 2);
+        assert_eq!(filtered.removed.len(), 1);
+        assert_eq!(filtered.removed[0].index, 1);
+        assert_eq!(
+            filtered.removed[0].reason,
+            FilterReason::Repetition {
+                ngram: "im sorry".to_string(),
+                count: 6
+            }
+        );
+    }
+}
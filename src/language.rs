@@ -0,0 +1,207 @@
+use std::ffi::c_int;
+use std::fmt;
+
+/// A language supported by Whisper.
+///
+/// This is a thin wrapper around the language ID used by whisper.cpp.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Language {
+    id: c_int,
+}
+
+impl Language {
+    /// Get the language with the specified short code (e.g. "de").
+    ///
+    /// # Returns
+    /// The language, None if whisper.cpp doesn't know the code.
+    ///
+    /// # C++ equivalent
+    /// `int whisper_lang_id(const char * lang)`
+    pub fn from_code(code: &str) -> Option<Self> {
+        if code.contains('\0') {
+            return None;
+        }
+        crate::standalone::get_lang_id(code).and_then(Self::from_id)
+    }
+
+    /// Get the language with the specified full name (e.g. "german").
+    ///
+    /// The comparison is case-insensitive.
+    ///
+    /// # Returns
+    /// The language, None if the name is unknown.
+    pub fn from_full_name(name: &str) -> Option<Self> {
+        FULL_NAMES
+            .iter()
+            .find(|(_, full_name)| full_name.eq_ignore_ascii_case(name))
+            .and_then(|(code, _)| Self::from_code(code))
+    }
+
+    /// Iterate over all languages supported by whisper.cpp, in ID order.
+    pub fn all() -> impl Iterator<Item = Self> {
+        (0..=crate::standalone::get_lang_max_id()).map(|id| Self { id })
+    }
+
+    /// Get the language with the specified ID.
+    ///
+    /// # Returns
+    /// The language, None if the ID is out of range.
+    pub fn from_id(id: c_int) -> Option<Self> {
+        if (0..=crate::standalone::get_lang_max_id()).contains(&id) {
+            Some(Self { id })
+        } else {
+            None
+        }
+    }
+
+    /// Get the ID of this language, as used by whisper.cpp.
+    #[inline]
+    pub fn id(&self) -> c_int {
+        self.id
+    }
+
+    /// Get the short code of this language (e.g. "de").
+    pub fn code(&self) -> &'static str {
+        crate::standalone::get_lang_str(self.id)
+            .expect("language ID is in range, so whisper.cpp should have a code for it")
+    }
+
+    /// Get the full English name of this language (e.g. "german").
+    ///
+    /// # Returns
+    /// The full name, None if this crate doesn't know the name of the language.
+    pub fn full_name(&self) -> Option<&'static str> {
+        let code = self.code();
+        FULL_NAMES
+            .iter()
+            .find(|(c, _)| *c == code)
+            .map(|(_, full_name)| *full_name)
+    }
+}
+
+impl fmt::Display for Language {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+/// Serialized as its short code (e.g. "de").
+#[cfg(feature = "serde")]
+impl serde::Serialize for Language {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.code())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Language {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let code = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+        Self::from_code(&code)
+            .ok_or_else(|| serde::de::Error::custom(format!("unknown language code {:?}", code)))
+    }
+}
+
+// whisper.cpp doesn't expose the full names of its languages, so they're mirrored here.
+// keep in sync with `g_lang` in whisper.cpp
+const FULL_NAMES: &[(&str, &str)] = &[
+    ("en", "english"),
+    ("zh", "chinese"),
+    ("de", "german"),
+    ("es", "spanish"),
+    ("ru", "russian"),
+    ("ko", "korean"),
+    ("fr", "french"),
+    ("ja", "japanese"),
+    ("pt", "portuguese"),
+    ("tr", "turkish"),
+    ("pl", "polish"),
+    ("ca", "catalan"),
+    ("nl", "dutch"),
+    ("ar", "arabic"),
+    ("sv", "swedish"),
+    ("it", "italian"),
+    ("id", "indonesian"),
+    ("hi", "hindi"),
+    ("fi", "finnish"),
+    ("vi", "vietnamese"),
+    ("iw", "hebrew"),
+    ("uk", "ukrainian"),
+    ("el", "greek"),
+    ("ms", "malay"),
+    ("cs", "czech"),
+    ("ro", "romanian"),
+    ("da", "danish"),
+    ("hu", "hungarian"),
+    ("ta", "tamil"),
+    ("no", "norwegian"),
+    ("th", "thai"),
+    ("ur", "urdu"),
+    ("hr", "croatian"),
+    ("bg", "bulgarian"),
+    ("lt", "lithuanian"),
+    ("la", "latin"),
+    ("mi", "maori"),
+    ("ml", "malayalam"),
+    ("cy", "welsh"),
+    ("sk", "slovak"),
+    ("te", "telugu"),
+    ("fa", "persian"),
+    ("lv", "latvian"),
+    ("bn", "bengali"),
+    ("sr", "serbian"),
+    ("az", "azerbaijani"),
+    ("sl", "slovenian"),
+    ("kn", "kannada"),
+    ("et", "estonian"),
+    ("mk", "macedonian"),
+    ("br", "breton"),
+    ("eu", "basque"),
+    ("is", "icelandic"),
+    ("hy", "armenian"),
+    ("ne", "nepali"),
+    ("mn", "mongolian"),
+    ("bs", "bosnian"),
+    ("kk", "kazakh"),
+    ("sq", "albanian"),
+    ("sw", "swahili"),
+    ("gl", "galician"),
+    ("mr", "marathi"),
+    ("pa", "punjabi"),
+    ("si", "sinhala"),
+    ("km", "khmer"),
+    ("sn", "shona"),
+    ("yo", "yoruba"),
+    ("so", "somali"),
+    ("af", "afrikaans"),
+    ("oc", "occitan"),
+    ("ka", "georgian"),
+    ("be", "belarusian"),
+    ("tg", "tajik"),
+    ("sd", "sindhi"),
+    ("gu", "gujarati"),
+    ("am", "amharic"),
+    ("yi", "yiddish"),
+    ("lo", "lao"),
+    ("uz", "uzbek"),
+    ("fo", "faroese"),
+    ("ht", "haitian creole"),
+    ("ps", "pashto"),
+    ("tk", "turkmen"),
+    ("nn", "nynorsk"),
+    ("mt", "maltese"),
+    ("sa", "sanskrit"),
+    ("lb", "luxembourgish"),
+    ("my", "myanmar"),
+    ("bo", "tibetan"),
+    ("tl", "tagalog"),
+    ("mg", "malagasy"),
+    ("as", "assamese"),
+    ("tt", "tatar"),
+    ("haw", "hawaiian"),
+    ("ln", "lingala"),
+    ("ha", "hausa"),
+    ("ba", "bashkir"),
+    ("jw", "javanese"),
+    ("su", "sundanese"),
+];
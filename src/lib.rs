@@ -1,17 +1,54 @@
 #![allow(clippy::uninlined_format_args)]
 #![cfg_attr(feature = "simd", feature(portable_simd))]
 
+pub mod batch;
+#[cfg(feature = "capture")]
+pub mod capture;
+pub mod channels;
+mod engine;
 mod error;
+pub mod hallucination;
+mod language;
+pub mod memory;
+mod model_header;
+#[cfg(feature = "download-models")]
+pub mod models;
+#[cfg(feature = "serde")]
+pub mod openai;
+mod pool;
+mod ring_buffer;
+mod segments;
 mod standalone;
+mod system_info;
+mod timestamp;
+mod transcribe_options;
+pub mod transcript;
 mod utilities;
+pub mod vad;
+#[cfg(feature = "async")]
+mod whisper_async;
 mod whisper_ctx;
 mod whisper_params;
+mod whisper_params_builder;
 
+pub use engine::{MockEngine, MockRequest, TranscriptionEngine};
 pub use error::WhisperError;
+pub use language::Language;
+pub use model_header::{GgmlFtype, ModelHeader};
+pub use pool::{PooledContext, WhisperContextPool};
+pub use ring_buffer::AudioRingBuffer;
+pub use segments::{TextFormat, WhisperSegment, WhisperSegmentTokens, WhisperSegments};
 pub use standalone::*;
+pub use system_info::SystemInfo;
+pub use timestamp::Timestamp;
+pub use transcribe_options::TranscribeOptions;
+pub use transcript::{ConfidenceStrategy, Segment, Token, Transcript, Word};
 pub use utilities::*;
-pub use whisper_ctx::WhisperContext;
-pub use whisper_params::{FullParams, SamplingStrategy};
+#[cfg(feature = "async")]
+pub use whisper_async::{FullStreamHandle, SegmentStream};
+pub use whisper_ctx::{FullThreadHandle, WhisperContext};
+pub use whisper_params::{default_n_threads, ConsoleOutput, FullParams, SamplingStrategy, Task};
+pub use whisper_params_builder::FullParamsBuilder;
 
 pub type WhisperTokenData = whisper_rs_sys::whisper_token_data;
 pub type WhisperToken = whisper_rs_sys::whisper_token;
@@ -0,0 +1,153 @@
+//! Estimates of how much memory a model needs, to check it fits before loading it.
+//!
+//! The figures mirror the fixed buffer sizes the bundled whisper.cpp allocates for each model type.
+
+use crate::{GgmlFtype, ModelHeader};
+use std::io;
+use std::path::Path;
+
+const MIB: u64 = 1024 * 1024;
+
+/// The size class of a Whisper model. Multilingual and English-only variants have the same size.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ModelType {
+    Tiny,
+    Base,
+    Small,
+    Medium,
+    Large,
+    /// A model with a number of audio encoder layers that no official model has.
+    Unknown(i32),
+}
+
+impl ModelType {
+    /// Get the model type from the number of audio encoder layers, as whisper.cpp does.
+    ///
+    /// # Returns
+    /// The model type, [ModelType::Unknown] with the number of layers if no official model has that many.
+    pub fn from_audio_layers(n_audio_layer: i32) -> Self {
+        match n_audio_layer {
+            4 => Self::Tiny,
+            6 => Self::Base,
+            12 => Self::Small,
+            24 => Self::Medium,
+            32 => Self::Large,
+            _ => Self::Unknown(n_audio_layer),
+        }
+    }
+
+    /// Estimate the memory a model of this type needs.
+    ///
+    /// # Arguments
+    /// * f16: Are the weights stored as 16 bit floats? This is the case for all official ggml models.
+    ///   32 bit models need twice the memory for most buffers.
+    ///
+    /// # Returns
+    /// The estimate, None for [ModelType::Unknown], as whisper.cpp has no buffer sizes for it.
+    pub fn estimate_memory(&self, f16: bool) -> Option<MemoryEstimate> {
+        // keep in sync with the MEM_REQ_* tables in whisper.cpp, in MiB
+        let (scratch, weights, kv_self, kv_cross, encode, decode) = match self {
+            Self::Tiny => (12 + 18 + 4 + 4, 74, 3, 9, 6, 3),
+            Self::Base => (15 + 24 + 4 + 4, 142, 6, 18, 8, 5),
+            Self::Small => (23 + 36 + 6 + 6, 466, 16, 53, 13, 10),
+            Self::Medium => (31 + 48 + 7 + 7, 1464, 43, 141, 22, 18),
+            Self::Large => (38 + 60 + 9 + 9, 2952, 71, 235, 33, 27),
+            Self::Unknown(_) => return None,
+        };
+        let scale = if f16 { 1 } else { 2 };
+        Some(MemoryEstimate {
+            model_type: *self,
+            weights: scale * weights * MIB,
+            kv_cross: scale * kv_cross * MIB,
+            kv_self: scale * kv_self * MIB,
+            compute: scale * u64::max(encode, decode) * MIB,
+            scratch: scratch * MIB,
+        })
+    }
+}
+
+/// How much memory a model needs, in bytes.
+///
+/// Get this with [estimate_memory] or [ModelType::estimate_memory].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct MemoryEstimate {
+    /// The type of the model.
+    pub model_type: ModelType,
+    /// The model weights.
+    pub weights: u64,
+    /// The cross-attention cache, allocated once.
+    pub kv_cross: u64,
+    /// The self-attention cache, allocated once for every decoder.
+    pub kv_self: u64,
+    /// The buffer used to run the encoder and decoder.
+    pub compute: u64,
+    /// Scratch buffers for intermediate results.
+    pub scratch: u64,
+}
+
+impl MemoryEstimate {
+    /// Total memory needed to load the model and run it with the given number of decoders.
+    ///
+    /// # Arguments
+    /// * n_decoders: How many sequences are decoded at once.
+    ///   This is `best_of` for [SamplingStrategy::Greedy](crate::SamplingStrategy::Greedy),
+    ///   and `beam_size` for [SamplingStrategy::BeamSearch](crate::SamplingStrategy::BeamSearch).
+    pub fn total(&self, n_decoders: u64) -> u64 {
+        self.weights
+            + self.kv_cross
+            + self.compute
+            + self.scratch
+            + self.kv_self * n_decoders.max(1)
+    }
+}
+
+/// Estimate how much memory a model file needs, by reading only its header.
+///
+/// # Arguments
+/// * path: The path to the model file.
+///
+/// # Returns
+/// Ok(MemoryEstimate) on success, Err(io::Error) if the file couldn't be read or isn't a known ggml Whisper model.
+pub fn estimate_memory(path: impl AsRef<Path>) -> io::Result<MemoryEstimate> {
+    let header = ModelHeader::read(path)?;
+    header
+        .model_type()
+        .estimate_memory(header.ftype != GgmlFtype::F32)
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "unknown model type with {} audio layers",
+                    header.n_audio_layer
+                ),
+            )
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn assert_estimate_from_header() {
+        let header = crate::model_header::test::header(6, 80);
+        // unique per process, so concurrent test runs don't share the file
+        let path =
+            std::env::temp_dir().join(format!("whisper-rs-memory-test-{}.bin", std::process::id()));
+        std::fs::write(&path, &header).unwrap();
+        let estimate = estimate_memory(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        let estimate = estimate.unwrap();
+        assert_eq!(Some(estimate), ModelType::Base.estimate_memory(true));
+        assert_eq!(estimate.total(1), (142 + 18 + 8 + 47 + 6) * MIB);
+        assert_eq!(estimate.total(5), (142 + 18 + 8 + 47 + 5 * 6) * MIB);
+    }
+
+    #[test]
+    fn assert_unknown_model_type() {
+        assert_eq!(ModelType::from_audio_layers(32), ModelType::Large);
+        assert_eq!(ModelType::from_audio_layers(2), ModelType::Unknown(2));
+        assert_eq!(ModelType::Unknown(2).estimate_memory(true), None);
+    }
+}
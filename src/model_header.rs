@@ -0,0 +1,176 @@
+use crate::memory::ModelType;
+use crate::WhisperError;
+use std::ffi::c_int;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+/// Magic number at the start of ggml model files.
+const GGML_MAGIC: u32 = 0x67676d6c;
+
+/// Size of the header, which is the magic number followed by [ModelHeader]'s fields.
+const HEADER_LEN: usize = 48;
+
+/// The hyperparameters at the start of a ggml model file, which can be read without loading the model.
+///
+/// Use this to check that a model works with the bundled whisper.cpp before loading it,
+/// see [ModelHeader::check_compatible].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ModelHeader {
+    pub n_vocab: c_int,
+    pub n_audio_ctx: c_int,
+    pub n_audio_state: c_int,
+    pub n_audio_head: c_int,
+    pub n_audio_layer: c_int,
+    pub n_text_ctx: c_int,
+    pub n_text_state: c_int,
+    pub n_text_head: c_int,
+    pub n_text_layer: c_int,
+    /// Number of mel bands the model expects its input to have.
+    /// This is 80 for all models up to large-v2, and 128 for large-v3.
+    pub n_mels: c_int,
+    /// How the weights are stored.
+    pub ftype: GgmlFtype,
+}
+
+/// How the weights of a ggml model are stored, see [ModelHeader::ftype].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum GgmlFtype {
+    /// 32 bit floats.
+    F32,
+    /// 16 bit floats, like all official ggml models.
+    F16,
+    /// Any other type, e.g. one of the quantized types of newer whisper.cpp versions, which the bundled one can't load.
+    Unknown(i32),
+}
+
+impl From<c_int> for GgmlFtype {
+    fn from(ftype: c_int) -> Self {
+        match ftype {
+            0 => Self::F32,
+            1 => Self::F16,
+            _ => Self::Unknown(ftype),
+        }
+    }
+}
+
+impl ModelHeader {
+    /// Read the header of a model file.
+    ///
+    /// # Arguments
+    /// * path: The path to the model file.
+    ///
+    /// # Returns
+    /// Ok(ModelHeader) on success, Err(io::Error) if the file couldn't be read or isn't a ggml model.
+    pub fn read(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut header = [0; HEADER_LEN];
+        File::open(path)?.read_exact(&mut header)?;
+        Self::parse(&header)
+    }
+
+    /// Parse the header at the start of a model, e.g. one passed to
+    /// [WhisperContext::new_from_buffer](crate::WhisperContext::new_from_buffer).
+    ///
+    /// # Returns
+    /// Ok(ModelHeader) on success, Err(io::Error) if the buffer is too short or isn't a ggml model.
+    pub fn parse(buffer: &[u8]) -> io::Result<Self> {
+        if buffer.len() < HEADER_LEN {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "too short for a ggml model header",
+            ));
+        }
+        let field = |i: usize| i32::from_le_bytes(buffer[i * 4..i * 4 + 4].try_into().unwrap());
+        if field(0) as u32 != GGML_MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a ggml model file (bad magic)",
+            ));
+        }
+        Ok(Self {
+            n_vocab: field(1),
+            n_audio_ctx: field(2),
+            n_audio_state: field(3),
+            n_audio_head: field(4),
+            n_audio_layer: field(5),
+            n_text_ctx: field(6),
+            n_text_state: field(7),
+            n_text_head: field(8),
+            n_text_layer: field(9),
+            n_mels: field(10),
+            ftype: GgmlFtype::from(field(11)),
+        })
+    }
+
+    /// The type of the model, [ModelType::Unknown] if no official model has this many audio layers.
+    pub fn model_type(&self) -> ModelType {
+        ModelType::from_audio_layers(self.n_audio_layer)
+    }
+
+    /// Check that the bundled whisper.cpp can run this model.
+    ///
+    /// It only computes spectrograms with [WHISPER_N_MEL](whisper_rs_sys::WHISPER_N_MEL) mel bands,
+    /// and aborts the process when running a model that expects a different number (e.g. large-v3).
+    ///
+    /// # Returns
+    /// Ok(()) if the model is supported, Err(WhisperError::UnsupportedMelBands) otherwise.
+    pub fn check_compatible(&self) -> Result<(), WhisperError> {
+        check_mel_bands(self.n_mels)
+    }
+}
+
+pub(crate) fn check_mel_bands(n_mels: c_int) -> Result<(), WhisperError> {
+    if n_mels == whisper_rs_sys::WHISPER_N_MEL as c_int {
+        Ok(())
+    } else {
+        Err(WhisperError::UnsupportedMelBands { model: n_mels })
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+
+    /// The header of a ggml model with the given number of audio layers and mel bands.
+    pub(crate) fn header(n_audio_layer: i32, n_mels: i32) -> Vec<u8> {
+        let mut header = Vec::new();
+        header.extend(GGML_MAGIC.to_le_bytes());
+        for value in [
+            51865,
+            1500,
+            512,
+            8,
+            n_audio_layer,
+            448,
+            512,
+            8,
+            6,
+            n_mels,
+            1,
+        ] {
+            header.extend(i32::to_le_bytes(value));
+        }
+        header
+    }
+
+    #[test]
+    fn assert_parses_header() {
+        let parsed = ModelHeader::parse(&header(6, 80)).unwrap();
+        assert_eq!(parsed.n_audio_layer, 6);
+        assert_eq!(parsed.model_type(), ModelType::Base);
+        assert_eq!(parsed.ftype, GgmlFtype::F16);
+        assert!(parsed.check_compatible().is_ok());
+
+        let large_v3 = ModelHeader::parse(&header(32, 128)).unwrap();
+        assert!(matches!(
+            large_v3.check_compatible(),
+            Err(WhisperError::UnsupportedMelBands { model: 128 })
+        ));
+        assert!(ModelHeader::parse(&header(6, 80)[..40]).is_err());
+
+        let mut quantized = header(6, 80);
+        quantized[44..48].copy_from_slice(&2i32.to_le_bytes());
+        let quantized = ModelHeader::parse(&quantized).unwrap();
+        assert_eq!(quantized.ftype, GgmlFtype::Unknown(2));
+    }
+}
@@ -0,0 +1,301 @@
+//! Catalog of the official ggml Whisper models and a downloader for them.
+//!
+//! Only available with the `download-models` feature.
+//!
+//! ```no_run
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let model = whisper_rs::models::find("base.en").expect("known model");
+//! let path = model.download(|downloaded, total| {
+//!     eprintln!("{} / {:?} bytes", downloaded, total);
+//! })?;
+//! let ctx = whisper_rs::WhisperContext::new(&path.to_string_lossy()).expect("failed to load model");
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::memory::{MemoryEstimate, ModelType};
+use sha1::{Digest, Sha1};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// Where models are downloaded from. `{name}` is replaced by the model name.
+const BASE_URL: &str = "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-{name}.bin";
+
+/// A ggml model published by the whisper.cpp project.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Model {
+    /// Name of the model, e.g. "base.en".
+    pub name: &'static str,
+    /// Approximate size on disk, in MiB.
+    pub disk_size_mib: u64,
+    /// SHA-1 checksum of the model file, as a lowercase hex string.
+    pub sha1: &'static str,
+}
+
+/// All models known to work with the bundled whisper.cpp.
+pub const MODELS: &[Model] = &[
+    Model {
+        name: "tiny",
+        disk_size_mib: 75,
+        sha1: "bd577a113a864445d4c299885e0cb97d4ba92b5f",
+    },
+    Model {
+        name: "tiny.en",
+        disk_size_mib: 75,
+        sha1: "c78c86eb1a8faa21b369bcd33207cc90d64ae9df",
+    },
+    Model {
+        name: "base",
+        disk_size_mib: 142,
+        sha1: "465707469ff3a37a2b9b8d8f89f2f99de7299dac",
+    },
+    Model {
+        name: "base.en",
+        disk_size_mib: 142,
+        sha1: "137c40403d78fd54d454da0f9bd998f78703390c",
+    },
+    Model {
+        name: "small",
+        disk_size_mib: 466,
+        sha1: "55356645c2b361a969dfd0ef2c5a50d530afd8d5",
+    },
+    Model {
+        name: "small.en",
+        disk_size_mib: 466,
+        sha1: "db8a495a91d927739e50b3fc1cc4c6b8f6c2d022",
+    },
+    Model {
+        name: "medium",
+        disk_size_mib: 1536,
+        sha1: "fd9727b6e1217c2f614f9b698455c4ffd82463b4",
+    },
+    Model {
+        name: "medium.en",
+        disk_size_mib: 1536,
+        sha1: "8c30f0e44ce9560643ebd10bbe50cd20eafd3723",
+    },
+    Model {
+        name: "large-v1",
+        disk_size_mib: 2970,
+        sha1: "b1caaf735c4cc1429223d5a74f0f4d0b9b59a299",
+    },
+    Model {
+        name: "large-v2",
+        disk_size_mib: 2970,
+        sha1: "0f4c8e34f21cf1a914c59d8b3ce882345ad349d6",
+    },
+];
+
+/// Find a model by name, e.g. "base.en".
+pub fn find(name: &str) -> Option<&'static Model> {
+    MODELS.iter().find(|model| model.name == name)
+}
+
+/// The directory models are cached in.
+///
+/// This is `$WHISPER_RS_MODEL_DIR` if set, otherwise `whisper-rs` in the platform cache directory
+/// (e.g. `~/.cache/whisper-rs` on Linux).
+///
+/// # Returns
+/// The directory, None if no cache directory could be determined.
+pub fn cache_dir() -> Option<PathBuf> {
+    match std::env::var_os("WHISPER_RS_MODEL_DIR") {
+        Some(dir) => Some(PathBuf::from(dir)),
+        None => dirs::cache_dir().map(|dir| dir.join("whisper-rs")),
+    }
+}
+
+/// An error that occurred while downloading a model.
+#[derive(Debug)]
+pub enum DownloadError {
+    /// No cache directory could be determined, see [cache_dir].
+    NoCacheDir,
+    /// Reading from the network or writing to disk failed.
+    Io(io::Error),
+    /// The HTTP request failed.
+    Http(Box<ureq::Error>),
+    /// The downloaded file doesn't match the expected checksum.
+    ChecksumMismatch {
+        expected: &'static str,
+        actual: String,
+    },
+}
+
+impl fmt::Display for DownloadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoCacheDir => write!(f, "unable to determine a cache directory for models"),
+            Self::Io(e) => write!(f, "I/O error while downloading model: {}", e),
+            Self::Http(e) => write!(f, "HTTP error while downloading model: {}", e),
+            Self::ChecksumMismatch { expected, actual } => write!(
+                f,
+                "model checksum mismatch: expected {}, got {}",
+                expected, actual
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DownloadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Http(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for DownloadError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<ureq::Error> for DownloadError {
+    fn from(e: ureq::Error) -> Self {
+        Self::Http(Box::new(e))
+    }
+}
+
+impl Model {
+    /// File name of the model, e.g. "ggml-base.en.bin".
+    pub fn file_name(&self) -> String {
+        format!("ggml-{}.bin", self.name)
+    }
+
+    /// URL the model is downloaded from.
+    pub fn url(&self) -> String {
+        BASE_URL.replace("{name}", self.name)
+    }
+
+    /// The size class of the model, from the start of its name.
+    ///
+    /// # Returns
+    /// The model type, None if the name doesn't start with that of a size class, e.g. for a custom [Model].
+    pub fn model_type(&self) -> Option<ModelType> {
+        match self.name.split(['.', '-']).next() {
+            Some("tiny") => Some(ModelType::Tiny),
+            Some("base") => Some(ModelType::Base),
+            Some("small") => Some(ModelType::Small),
+            Some("medium") => Some(ModelType::Medium),
+            Some("large") => Some(ModelType::Large),
+            _ => None,
+        }
+    }
+
+    /// Estimate how much memory the model needs, without downloading it.
+    ///
+    /// # Returns
+    /// The estimate, None if the [Model::model_type] is unknown.
+    pub fn estimate_memory(&self) -> Option<MemoryEstimate> {
+        // all official models are stored as 16 bit floats
+        self.model_type()?.estimate_memory(true)
+    }
+
+    /// Path of the model in the [cache_dir], if it has already been downloaded.
+    pub fn cached_path(&self) -> Option<PathBuf> {
+        let path = cache_dir()?.join(self.file_name());
+        path.is_file().then_some(path)
+    }
+
+    /// Download the model into the [cache_dir], unless it's already there.
+    ///
+    /// See [Model::download_to] for details.
+    pub fn download(
+        &self,
+        progress: impl FnMut(u64, Option<u64>),
+    ) -> Result<PathBuf, DownloadError> {
+        let dir = cache_dir().ok_or(DownloadError::NoCacheDir)?;
+        self.download_to(&dir, progress)
+    }
+
+    /// Download the model into `dir`, unless it's already there.
+    ///
+    /// The file is first written next to its final location and only moved into place
+    /// once its checksum has been verified, so an interrupted download is never mistaken for a model.
+    ///
+    /// # Arguments
+    /// * dir: Directory to put the model in. Created if it does not exist.
+    /// * progress: Called after every chunk with the number of bytes downloaded so far,
+    ///   and the total size if the server reported it.
+    ///
+    /// # Returns
+    /// Ok(PathBuf) with the path to the model on success, Err(DownloadError) on failure.
+    pub fn download_to(
+        &self,
+        dir: &Path,
+        mut progress: impl FnMut(u64, Option<u64>),
+    ) -> Result<PathBuf, DownloadError> {
+        let path = dir.join(self.file_name());
+        if path.is_file() {
+            return Ok(path);
+        }
+        std::fs::create_dir_all(dir)?;
+
+        let response = ureq::get(&self.url()).call()?;
+        let total = response
+            .header("Content-Length")
+            .and_then(|len| len.parse().ok());
+        let mut reader = response.into_reader();
+
+        let part_path = dir.join(format!("{}.part", self.file_name()));
+        let mut file = File::create(&part_path)?;
+        let mut hasher = Sha1::new();
+        let mut buf = vec![0; 64 * 1024];
+        let mut downloaded = 0;
+        loop {
+            let n = match reader.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            };
+            file.write_all(&buf[..n])?;
+            hasher.update(&buf[..n]);
+            downloaded += n as u64;
+            progress(downloaded, total);
+        }
+        file.flush()?;
+        drop(file);
+
+        let actual = hasher
+            .finalize()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<String>();
+        if actual != self.sha1 {
+            _ = std::fs::remove_file(&part_path);
+            return Err(DownloadError::ChecksumMismatch {
+                expected: self.sha1,
+                actual,
+            });
+        }
+
+        std::fs::rename(&part_path, &path)?;
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn assert_catalog_model_types() {
+        for model in MODELS {
+            assert!(model.estimate_memory().is_some(), "{}", model.name);
+        }
+        assert_eq!(
+            find("large-v2").unwrap().model_type(),
+            Some(ModelType::Large)
+        );
+        let custom = Model {
+            name: "distil-whisper",
+            ..MODELS[0]
+        };
+        assert_eq!(custom.model_type(), None);
+    }
+}
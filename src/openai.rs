@@ -0,0 +1,190 @@
+//! Transcripts in the shape of the OpenAI transcription API's `verbose_json` response,
+//! for services that want to be drop-in replacements for it.
+//!
+//! Only available with the `serde` feature.
+//!
+//! ```no_run
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! # let transcript = whisper_rs::Transcript::default();
+//! let response = whisper_rs::openai::VerboseJson::from(&transcript);
+//! let body = serde_json::to_string(&response)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{Segment, Task, Transcript};
+
+/// A transcription response, as returned by the OpenAI API for `response_format=verbose_json`.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct VerboseJson {
+    /// "transcribe" or "translate".
+    pub task: String,
+    /// Full English name of the spoken language (e.g. "english"), empty if it was auto-detected.
+    pub language: String,
+    /// Duration of the transcribed audio in seconds.
+    /// Up to the end of the last segment if [Transcript::duration] is not set.
+    pub duration: f64,
+    /// Text of all segments.
+    pub text: String,
+    pub segments: Vec<VerboseJsonSegment>,
+    /// Only present when requested with [VerboseJson::with_words].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub words: Option<Vec<VerboseJsonWord>>,
+}
+
+/// A segment of a [VerboseJson] response.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct VerboseJsonSegment {
+    pub id: usize,
+    /// Start of the segment in mel frames of 10 ms each.
+    pub seek: i64,
+    /// Start time in seconds.
+    pub start: f64,
+    /// End time in seconds.
+    pub end: f64,
+    pub text: String,
+    /// IDs of the text tokens of the segment.
+    pub tokens: Vec<i32>,
+    /// Always 0.0, as whisper.cpp does not report which temperature a segment was decoded at.
+    pub temperature: f64,
+    /// Mean log probability of the text tokens.
+    pub avg_logprob: f64,
+    /// Ratio of the size of the text to its zlib-compressed size. High values indicate repetitive text.
+    /// Always 0.0 without the `compression-ratio` feature.
+    pub compression_ratio: f64,
+    /// Always 0.0, as whisper.cpp does not report it.
+    pub no_speech_prob: f64,
+}
+
+/// A word of a [VerboseJson] response.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct VerboseJsonWord {
+    pub word: String,
+    /// Start time in seconds. Only meaningful with token timestamps enabled.
+    pub start: f64,
+    /// End time in seconds. Only meaningful with token timestamps enabled.
+    pub end: f64,
+}
+
+impl VerboseJson {
+    /// Convert a transcript, including its words.
+    ///
+    /// Enable [FullParams::set_token_timestamps](crate::FullParams::set_token_timestamps)
+    /// for the words to have meaningful times.
+    pub fn with_words(transcript: &Transcript) -> Self {
+        let words = transcript
+            .segments
+            .iter()
+            .flat_map(Segment::words)
+            .map(|word| VerboseJsonWord {
+                word: word.text,
+                start: seconds(word.t0),
+                end: seconds(word.t1),
+            })
+            .collect();
+        Self {
+            words: Some(words),
+            ..Self::from(transcript)
+        }
+    }
+}
+
+impl From<&Transcript> for VerboseJson {
+    fn from(transcript: &Transcript) -> Self {
+        Self {
+            task: match transcript.task {
+                Task::Transcribe => "transcribe",
+                Task::Translate => "translate",
+            }
+            .to_string(),
+            language: transcript
+                .language
+                .map(|language| language.full_name().unwrap_or(language.code()))
+                .unwrap_or_default()
+                .to_string(),
+            duration: if transcript.duration.is_zero() {
+                transcript.segments.last().map_or(0.0, |s| seconds(s.t1))
+            } else {
+                transcript.duration.as_secs_f64()
+            },
+            text: transcript.text(),
+            segments: transcript
+                .segments
+                .iter()
+                .enumerate()
+                .map(|(id, segment)| convert_segment(id, segment))
+                .collect(),
+            words: None,
+        }
+    }
+}
+
+fn convert_segment(id: usize, segment: &Segment) -> VerboseJsonSegment {
+    VerboseJsonSegment {
+        id,
+        seek: segment.t0,
+        start: seconds(segment.t0),
+        end: seconds(segment.t1),
+        text: segment.text.clone(),
+        tokens: segment
+            .tokens
+            .iter()
+            .filter(|token| !token.special)
+            .map(|token| token.id)
+            .collect(),
+        temperature: 0.0,
+        avg_logprob: segment.avg_logprob().unwrap_or_default() as f64,
+        #[cfg(feature = "compression-ratio")]
+        compression_ratio: segment.compression_ratio() as f64,
+        #[cfg(not(feature = "compression-ratio"))]
+        compression_ratio: 0.0,
+        no_speech_prob: 0.0,
+    }
+}
+
+fn seconds(centiseconds: i64) -> f64 {
+    centiseconds as f64 / 100.0
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Token;
+
+    #[test]
+    fn assert_converts_transcript() {
+        let token = |id, special, plog: f32| Token {
+            id,
+            special,
+            text: String::new(),
+            p: plog.exp(),
+            plog,
+            t0: 0,
+            t1: 0,
+        };
+        let transcript = Transcript {
+            segments: vec![Segment {
+                text: " Hello hello hello hello hello.".to_string(),
+                t0: 50,
+                t1: 250,
+                tokens: vec![
+                    token(50364, true, 0.0),
+                    token(1, false, -0.5),
+                    token(2, false, -1.5),
+                ],
+            }],
+            ..Default::default()
+        };
+
+        let json = VerboseJson::from(&transcript);
+        assert_eq!(json.task, "transcribe");
+        assert_eq!(json.duration, 2.5);
+        let segment = &json.segments[0];
+        assert_eq!((segment.start, segment.end), (0.5, 2.5));
+        assert_eq!(segment.tokens, [1, 2]);
+        assert_eq!(segment.avg_logprob, -1.0);
+        #[cfg(feature = "compression-ratio")]
+        assert!(segment.compression_ratio > 1.0);
+        assert!(json.words.is_none());
+    }
+}
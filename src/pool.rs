@@ -0,0 +1,117 @@
+use crate::{WhisperContext, WhisperError};
+use std::ops::{Deref, DerefMut};
+use std::sync::{Condvar, Mutex};
+
+/// A fixed-size pool of [WhisperContext]s for running several transcriptions concurrently.
+///
+/// Each context holds its own copy of the model, as the bundled whisper.cpp keeps the decoding state
+/// inside the context. Size the pool according to the memory available.
+///
+/// Contexts are borrowed with [WhisperContextPool::get], which blocks until one is available,
+/// and return to the pool when the guard is dropped.
+#[derive(Debug)]
+pub struct WhisperContextPool {
+    contexts: Mutex<Vec<WhisperContext>>,
+    available: Condvar,
+    size: usize,
+}
+
+impl WhisperContextPool {
+    /// Create a pool of `size` contexts, all loaded from the same model file.
+    ///
+    /// # Arguments
+    /// * path: The path to the model file.
+    /// * size: Number of contexts to create.
+    ///
+    /// # Returns
+    /// Ok(Self) on success, Err(WhisperError) if any of the contexts failed to load.
+    pub fn new(path: &str, size: usize) -> Result<Self, WhisperError> {
+        let contexts = (0..size)
+            .map(|_| WhisperContext::new(path))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self::from_contexts(contexts))
+    }
+
+    /// Create a pool from already loaded contexts.
+    pub fn from_contexts(contexts: Vec<WhisperContext>) -> Self {
+        Self {
+            size: contexts.len(),
+            contexts: Mutex::new(contexts),
+            available: Condvar::new(),
+        }
+    }
+
+    /// Total number of contexts in the pool, including the ones currently borrowed.
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Borrow a context, blocking until one is available.
+    ///
+    /// # Panics
+    /// Panics if the pool is empty, as this would block forever.
+    pub fn get(&self) -> PooledContext<'_> {
+        assert!(self.size > 0, "cannot borrow from an empty context pool");
+        let mut contexts = self.lock();
+        loop {
+            if let Some(ctx) = contexts.pop() {
+                return PooledContext {
+                    pool: self,
+                    ctx: Some(ctx),
+                };
+            }
+            contexts = self
+                .available
+                .wait(contexts)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+    }
+
+    /// Borrow a context if one is available right now.
+    pub fn try_get(&self) -> Option<PooledContext<'_>> {
+        self.lock().pop().map(|ctx| PooledContext {
+            pool: self,
+            ctx: Some(ctx),
+        })
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<WhisperContext>> {
+        // the vector is never left in an inconsistent state, so a poisoned lock is fine to reuse
+        self.contexts.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// A [WhisperContext] borrowed from a [WhisperContextPool].
+///
+/// Returns the context to the pool when dropped, after [resetting](WhisperContext::reset) it
+/// so the text of one transcription doesn't carry over into the next.
+#[derive(Debug)]
+pub struct PooledContext<'a> {
+    pool: &'a WhisperContextPool,
+    ctx: Option<WhisperContext>,
+}
+
+impl Deref for PooledContext<'_> {
+    type Target = WhisperContext;
+
+    fn deref(&self) -> &Self::Target {
+        self.ctx.as_ref().expect("context is only taken on drop")
+    }
+}
+
+impl DerefMut for PooledContext<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.ctx.as_mut().expect("context is only taken on drop")
+    }
+}
+
+impl Drop for PooledContext<'_> {
+    fn drop(&mut self) {
+        if let Some(mut ctx) = self.ctx.take() {
+            ctx.reset();
+            self.pool.lock().push(ctx);
+            self.pool.available.notify_one();
+        }
+    }
+}
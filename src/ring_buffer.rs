@@ -0,0 +1,130 @@
+use std::collections::VecDeque;
+
+/// A fixed-size buffer of audio samples, for transcribing a stream in overlapping windows.
+///
+/// Samples are appended with [AudioRingBuffer::push]. Once the buffer is full, the oldest samples are discarded.
+/// [AudioRingBuffer::drain_window] then hands out windows to pass to [WhisperContext::full](crate::WhisperContext::full),
+/// keeping the end of each window around as the start of the next one so words at the edges are not cut off.
+///
+/// This does no locking of its own; wrap it in a [Mutex](std::sync::Mutex) to share it with an audio callback.
+#[derive(Debug, Clone)]
+pub struct AudioRingBuffer {
+    samples: VecDeque<f32>,
+    capacity: usize,
+}
+
+impl AudioRingBuffer {
+    /// Create a buffer holding up to the given amount of 16 kHz audio.
+    ///
+    /// # Arguments
+    /// * seconds: How many seconds of audio to hold.
+    pub fn new(seconds: f32) -> Self {
+        let sample_rate = whisper_rs_sys::WHISPER_SAMPLE_RATE as f32;
+        Self::with_capacity((seconds.max(0.0) * sample_rate) as usize)
+    }
+
+    /// Create a buffer holding up to `capacity` samples.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            samples: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Maximum number of samples held.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Number of samples currently held.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Is the buffer empty?
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Append samples, discarding the oldest ones if the buffer would overflow.
+    pub fn push(&mut self, samples: &[f32]) {
+        let samples = &samples[samples.len().saturating_sub(self.capacity)..];
+        let overflow = (self.samples.len() + samples.len()).saturating_sub(self.capacity);
+        self.samples.drain(..overflow);
+        self.samples.extend(samples);
+    }
+
+    /// Take the oldest `window_len` samples, if that many are held.
+    ///
+    /// The last `keep_len` samples of the window stay in the buffer, and become the start of the next window.
+    ///
+    /// # Arguments
+    /// * window_len: Number of samples to return.
+    /// * keep_len: Number of samples at the end of the window to keep. Must be less than `window_len`.
+    ///
+    /// # Returns
+    /// The window, None if fewer than `window_len` samples are held.
+    ///
+    /// # Panics
+    /// Panics if `keep_len` is not less than `window_len`, as the buffer would never advance.
+    pub fn drain_window(&mut self, window_len: usize, keep_len: usize) -> Option<Vec<f32>> {
+        assert!(
+            keep_len < window_len,
+            "keep_len ({}) must be less than window_len ({})",
+            keep_len,
+            window_len
+        );
+        if self.samples.len() < window_len {
+            return None;
+        }
+        let window = self.samples.range(..window_len).copied().collect();
+        self.samples.drain(..window_len - keep_len);
+        Some(window)
+    }
+
+    /// Take all samples, leaving the buffer empty.
+    pub fn take_all(&mut self) -> Vec<f32> {
+        self.samples.drain(..).collect()
+    }
+
+    /// Copy the most recent `n` samples, or fewer if not as many are held, leaving the buffer untouched.
+    pub fn latest(&self, n: usize) -> Vec<f32> {
+        let skip = self.samples.len().saturating_sub(n);
+        self.samples.iter().skip(skip).copied().collect()
+    }
+
+    /// Discard all samples.
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn assert_discards_oldest() {
+        let mut buf = AudioRingBuffer::with_capacity(4);
+        buf.push(&[1.0, 2.0, 3.0]);
+        buf.push(&[4.0, 5.0]);
+        assert_eq!(buf.latest(10), [2.0, 3.0, 4.0, 5.0]);
+        buf.push(&[6.0, 7.0, 8.0, 9.0, 10.0]);
+        assert_eq!(buf.take_all(), [7.0, 8.0, 9.0, 10.0]);
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn assert_drain_window_overlaps() {
+        let mut buf = AudioRingBuffer::with_capacity(16);
+        buf.push(&[1.0, 2.0, 3.0, 4.0, 5.0]);
+        assert_eq!(buf.drain_window(4, 1), Some(vec![1.0, 2.0, 3.0, 4.0]));
+        assert_eq!(buf.drain_window(4, 1), None);
+        buf.push(&[6.0, 7.0]);
+        assert_eq!(buf.drain_window(4, 1), Some(vec![4.0, 5.0, 6.0, 7.0]));
+        assert_eq!(buf.len(), 1);
+    }
+}
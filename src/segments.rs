@@ -0,0 +1,230 @@
+use crate::{Segment, Timestamp, WhisperContext, WhisperError, WhisperToken, WhisperTokenData};
+use std::ffi::c_int;
+use std::iter::FusedIterator;
+use std::ops::Range;
+
+/// A segment of the last call to [WhisperContext::full], read directly from the context.
+///
+/// Get these with [WhisperContext::segments].
+/// Unlike [Segment], this borrows the context, so it can't outlive the next run of the model.
+#[derive(Debug, Copy, Clone)]
+pub struct WhisperSegment<'a> {
+    ctx: &'a WhisperContext,
+    index: c_int,
+}
+
+impl<'a> WhisperSegment<'a> {
+    /// Index of the segment.
+    #[inline]
+    pub fn index(&self) -> c_int {
+        self.index
+    }
+
+    /// Text of the segment.
+    ///
+    /// # Returns
+    /// Ok(String) on success, Err(WhisperError) on failure.
+    pub fn text(&self) -> Result<String, WhisperError> {
+        self.ctx.full_get_segment_text(self.index)
+    }
+
+    /// Text of the segment, borrowed from the context instead of copied.
+    ///
+    /// # Returns
+    /// Ok(&str) on success, Err(WhisperError) on failure.
+    pub fn text_str(&self) -> Result<&'a str, WhisperError> {
+        self.ctx.full_get_segment_text_str(self.index)
+    }
+
+    /// Text of the segment, rebuilt from its tokens with only the special tokens `format` asks for.
+    ///
+    /// Special tokens are written the way whisper.cpp's vocabulary names them,
+    /// e.g. `[_BEG_]` and `[_TT_150]` for the timestamps at 0.0 and 3.0 seconds.
+    ///
+    /// # Arguments
+    /// * format: Which special tokens to keep. The default leaves all of them out,
+    ///   even if [FullParams::set_print_special](crate::FullParams::set_print_special) put them into [WhisperSegment::text].
+    ///
+    /// # Returns
+    /// Ok(String) on success, Err(WhisperError) on failure.
+    pub fn text_with(&self, format: TextFormat) -> Result<String, WhisperError> {
+        let (eot, beg) = (self.ctx.token_eot(), self.ctx.token_beg());
+        let tokens: Vec<WhisperToken> = self
+            .tokens()
+            .map(|token| token.id)
+            .filter(|&id| format.keeps(id, eot, beg))
+            .collect();
+        self.ctx.detokenize(&tokens)
+    }
+
+    /// Start time of the segment.
+    #[inline]
+    pub fn start(&self) -> Timestamp {
+        Timestamp::from_centiseconds(self.ctx.full_get_segment_t0(self.index))
+    }
+
+    /// End time of the segment.
+    #[inline]
+    pub fn end(&self) -> Timestamp {
+        Timestamp::from_centiseconds(self.ctx.full_get_segment_t1(self.index))
+    }
+
+    /// Number of tokens in the segment.
+    #[inline]
+    pub fn n_tokens(&self) -> c_int {
+        self.ctx.full_n_tokens(self.index)
+    }
+
+    /// Iterate over the data of the tokens in the segment.
+    pub fn tokens(&self) -> WhisperSegmentTokens<'a> {
+        WhisperSegmentTokens {
+            ctx: self.ctx,
+            segment: self.index,
+            range: 0..self.n_tokens().max(0),
+        }
+    }
+
+    /// Copy the segment into an owned [Segment].
+    ///
+    /// # Returns
+    /// Ok(Segment) on success, Err(WhisperError) on failure.
+    pub fn to_segment(&self) -> Result<Segment, WhisperError> {
+        self.ctx.full_get_segment(self.index)
+    }
+}
+
+/// Which special tokens [WhisperSegment::text_with] keeps in the text.
+///
+/// The bundled whisper.cpp has no speaker turn detection, so there are no speaker turn markers to keep.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct TextFormat {
+    /// Keep timestamp tokens, such as `[_BEG_]` or `[_TT_150]`.
+    pub timestamps: bool,
+    /// Keep all other special tokens, such as `[_EOT_]` or the language and task tokens.
+    pub special: bool,
+}
+
+impl TextFormat {
+    /// Format that keeps all special tokens.
+    pub const ALL: Self = Self {
+        timestamps: true,
+        special: true,
+    };
+
+    /// Should the token be part of the text?
+    ///
+    /// # Arguments
+    /// * token: The token to check.
+    /// * eot: The first special token, [WhisperContext::token_eot].
+    /// * beg: The first timestamp token, [WhisperContext::token_beg].
+    fn keeps(&self, token: WhisperToken, eot: WhisperToken, beg: WhisperToken) -> bool {
+        if token >= beg {
+            self.timestamps
+        } else if token >= eot {
+            self.special
+        } else {
+            true
+        }
+    }
+}
+
+/// Iterator over the segments of the last call to [WhisperContext::full].
+///
+/// Created by [WhisperContext::segments].
+#[derive(Debug, Clone)]
+pub struct WhisperSegments<'a> {
+    ctx: &'a WhisperContext,
+    range: Range<c_int>,
+}
+
+impl<'a> WhisperSegments<'a> {
+    pub(crate) fn new(ctx: &'a WhisperContext) -> Self {
+        Self {
+            ctx,
+            range: 0..ctx.full_n_segments().max(0),
+        }
+    }
+
+    fn segment(&self, index: c_int) -> WhisperSegment<'a> {
+        WhisperSegment {
+            ctx: self.ctx,
+            index,
+        }
+    }
+}
+
+impl<'a> Iterator for WhisperSegments<'a> {
+    type Item = WhisperSegment<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.range.next().map(|index| self.segment(index))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.range.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for WhisperSegments<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.range.next_back().map(|index| self.segment(index))
+    }
+}
+
+impl ExactSizeIterator for WhisperSegments<'_> {}
+
+impl FusedIterator for WhisperSegments<'_> {}
+
+/// Iterator over the data of the tokens in a [WhisperSegment].
+///
+/// Created by [WhisperSegment::tokens].
+#[derive(Debug, Clone)]
+pub struct WhisperSegmentTokens<'a> {
+    ctx: &'a WhisperContext,
+    segment: c_int,
+    range: Range<c_int>,
+}
+
+impl Iterator for WhisperSegmentTokens<'_> {
+    type Item = WhisperTokenData;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let token = self.range.next()?;
+        Some(self.ctx.full_get_token_data(self.segment, token))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.range.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for WhisperSegmentTokens<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let token = self.range.next_back()?;
+        Some(self.ctx.full_get_token_data(self.segment, token))
+    }
+}
+
+impl ExactSizeIterator for WhisperSegmentTokens<'_> {}
+
+impl FusedIterator for WhisperSegmentTokens<'_> {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn assert_text_format_keeps() {
+        let (eot, beg) = (50256, 50363);
+        let timestamps = TextFormat {
+            timestamps: true,
+            ..Default::default()
+        };
+        assert!(TextFormat::default().keeps(42, eot, beg));
+        assert!(!TextFormat::default().keeps(eot, eot, beg));
+        assert!(!TextFormat::default().keeps(beg + 150, eot, beg));
+        assert!(timestamps.keeps(beg, eot, beg));
+        assert!(!timestamps.keeps(eot, eot, beg));
+        assert!(TextFormat::ALL.keeps(eot, eot, beg));
+    }
+}
@@ -1,6 +1,6 @@
 //! Standalone functions that have no associated type.
 
-use crate::WhisperToken;
+use crate::{SystemInfo, WhisperToken};
 use std::ffi::{c_int, CStr, CString};
 
 /// Return the id of the specified language, returns -1 if not found
@@ -80,3 +80,37 @@ pub fn print_system_info() -> &'static str {
     let c_str = unsafe { CStr::from_ptr(c_buf) };
     c_str.to_str().unwrap()
 }
+
+/// Get the CPU features whisper.cpp was compiled with.
+///
+/// This parses the output of [print_system_info].
+///
+/// # C++ equivalent
+/// `const char * whisper_print_system_info()`
+pub fn system_info() -> SystemInfo {
+    SystemInfo::parse(print_system_info())
+}
+
+/// Benchmark memcpy throughput, printing the results to stderr.
+///
+/// # Arguments
+/// * n_threads: How many threads to use.
+///
+/// # C++ equivalent
+/// `int whisper_bench_memcpy(int n_threads)`
+pub fn bench_memcpy(n_threads: c_int) {
+    unsafe { whisper_rs_sys::whisper_bench_memcpy(n_threads) };
+}
+
+/// Benchmark ggml matrix multiplication at several sizes, printing the results to stderr.
+///
+/// This can take a while to run.
+///
+/// # Arguments
+/// * n_threads: How many threads to use.
+///
+/// # C++ equivalent
+/// `int whisper_bench_ggml_mul_mat(int n_threads)`
+pub fn bench_ggml_mul_mat(n_threads: c_int) {
+    unsafe { whisper_rs_sys::whisper_bench_ggml_mul_mat(n_threads) };
+}
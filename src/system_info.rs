@@ -0,0 +1,72 @@
+/// CPU features whisper.cpp was compiled with.
+///
+/// Get this with [system_info](crate::system_info).
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct SystemInfo {
+    pub avx: bool,
+    pub avx2: bool,
+    pub avx512: bool,
+    pub fma: bool,
+    pub neon: bool,
+    pub arm_fma: bool,
+    pub f16c: bool,
+    pub fp16_va: bool,
+    pub wasm_simd: bool,
+    /// Was whisper.cpp built with a BLAS library (OpenBLAS or Accelerate)?
+    pub blas: bool,
+    pub sse3: bool,
+    pub vsx: bool,
+}
+
+impl SystemInfo {
+    /// Parse the output of [print_system_info](crate::print_system_info),
+    /// e.g. `AVX = 1 | AVX2 = 0 | ...`.
+    ///
+    /// Unknown keys are ignored, and missing keys are left as false.
+    pub fn parse(info: &str) -> Self {
+        let mut ret = Self::default();
+        for (key, value) in info.split('|').filter_map(|entry| entry.split_once('=')) {
+            let value = value.trim() == "1";
+            match key.trim() {
+                "AVX" => ret.avx = value,
+                "AVX2" => ret.avx2 = value,
+                "AVX512" => ret.avx512 = value,
+                "FMA" => ret.fma = value,
+                "NEON" => ret.neon = value,
+                "ARM_FMA" => ret.arm_fma = value,
+                "F16C" => ret.f16c = value,
+                "FP16_VA" => ret.fp16_va = value,
+                "WASM_SIMD" => ret.wasm_simd = value,
+                "BLAS" => ret.blas = value,
+                "SSE3" => ret.sse3 = value,
+                "VSX" => ret.vsx = value,
+                _ => {}
+            }
+        }
+        ret
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn assert_parse_system_info() {
+        let info = SystemInfo::parse(
+            "AVX = 1 | AVX2 = 1 | AVX512 = 0 | FMA = 1 | NEON = 0 | ARM_FMA = 0 | F16C = 1 | \
+            FP16_VA = 0 | WASM_SIMD = 0 | BLAS = 0 | SSE3 = 1 | VSX = 0 | ",
+        );
+        assert_eq!(
+            info,
+            SystemInfo {
+                avx: true,
+                avx2: true,
+                fma: true,
+                f16c: true,
+                sse3: true,
+                ..Default::default()
+            }
+        );
+    }
+}
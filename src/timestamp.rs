@@ -0,0 +1,106 @@
+use std::fmt;
+use std::time::Duration;
+
+/// A point in the audio, as reported by whisper.cpp in centiseconds.
+///
+/// Displays as `HH:MM:SS.mmm`.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Timestamp {
+    centiseconds: i64,
+}
+
+impl Timestamp {
+    /// Create a timestamp from centiseconds, e.g. the return value of
+    /// [WhisperContext::full_get_segment_t0](crate::WhisperContext::full_get_segment_t0).
+    #[inline]
+    pub fn from_centiseconds(centiseconds: i64) -> Self {
+        Self { centiseconds }
+    }
+
+    /// The timestamp in centiseconds.
+    #[inline]
+    pub fn as_centiseconds(&self) -> i64 {
+        self.centiseconds
+    }
+
+    /// The timestamp in milliseconds.
+    #[inline]
+    pub fn as_millis(&self) -> i64 {
+        self.centiseconds * 10
+    }
+
+    /// The timestamp as a [Duration]. Negative timestamps are clamped to zero.
+    #[inline]
+    pub fn as_duration(&self) -> Duration {
+        Duration::from_millis(self.as_millis().max(0) as u64)
+    }
+
+    /// Format the timestamp as used by SRT subtitles, e.g. `00:01:02,340`.
+    pub fn to_srt(&self) -> String {
+        self.format(',')
+    }
+
+    /// Format the timestamp as used by WebVTT subtitles, e.g. `00:01:02.340`.
+    pub fn to_vtt(&self) -> String {
+        self.format('.')
+    }
+
+    /// Format the timestamp as used by LRC lyrics, e.g. `62:02.34`. Minutes are not wrapped into hours.
+    pub fn to_lrc(&self) -> String {
+        let cs = self.centiseconds.max(0);
+        format!("{:02}:{:02}.{:02}", cs / 6000, cs / 100 % 60, cs % 100)
+    }
+
+    /// Format the timestamp as used by ASS subtitles, e.g. `1:02:02.34`.
+    pub fn to_ass(&self) -> String {
+        let cs = self.centiseconds.max(0);
+        format!(
+            "{}:{:02}:{:02}.{:02}",
+            cs / 360_000,
+            cs / 6000 % 60,
+            cs / 100 % 60,
+            cs % 100
+        )
+    }
+
+    fn format(&self, separator: char) -> String {
+        let ms = self.as_millis().max(0);
+        format!(
+            "{:02}:{:02}:{:02}{}{:03}",
+            ms / 3_600_000,
+            ms / 60_000 % 60,
+            ms / 1000 % 60,
+            separator,
+            ms % 1000
+        )
+    }
+}
+
+impl From<Timestamp> for Duration {
+    fn from(timestamp: Timestamp) -> Self {
+        timestamp.as_duration()
+    }
+}
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_vtt())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn assert_formats() {
+        let ts = Timestamp::from_centiseconds(372_234);
+        assert_eq!(ts.to_srt(), "01:02:02,340");
+        assert_eq!(ts.to_vtt(), "01:02:02.340");
+        assert_eq!(ts.as_duration(), Duration::from_millis(3_722_340));
+        assert_eq!(ts.to_lrc(), "62:02.34");
+        assert_eq!(ts.to_ass(), "1:02:02.34");
+        assert_eq!(Timestamp::from_centiseconds(-5).to_srt(), "00:00:00,000");
+    }
+}
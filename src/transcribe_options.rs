@@ -0,0 +1,214 @@
+use crate::{ConsoleOutput, FullParams, FullParamsBuilder, SamplingStrategy, WhisperError};
+use std::ffi::c_int;
+
+/// Plain-data transcription options that can be converted into [FullParams].
+///
+/// Every field left as None keeps the whisper.cpp default.
+/// With the `serde` feature, this can be loaded from configuration files and logged as-is:
+/// missing fields are filled in from [Default].
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct TranscribeOptions {
+    /// Sampling strategy to create the parameters with.
+    pub sampling_strategy: SamplingStrategy,
+    /// See [FullParams::set_language]. Use "auto" for auto-detection.
+    pub language: Option<String>,
+    /// See [FullParams::set_initial_prompt].
+    pub initial_prompt: Option<String>,
+    /// See [FullParams::set_n_threads].
+    pub n_threads: Option<c_int>,
+    /// See [FullParams::set_n_max_text_ctx].
+    pub n_max_text_ctx: Option<c_int>,
+    /// See [FullParams::set_offset_ms].
+    pub offset_ms: Option<c_int>,
+    /// See [FullParams::set_duration_ms].
+    pub duration_ms: Option<c_int>,
+    /// See [FullParams::set_translate].
+    pub translate: Option<bool>,
+    /// See [FullParams::set_no_context].
+    pub no_context: Option<bool>,
+    /// See [FullParams::set_single_segment].
+    pub single_segment: Option<bool>,
+    /// See [FullParams::set_console_output]. The individual `print_*` options below take precedence over it.
+    pub console_output: Option<ConsoleOutput>,
+    /// See [FullParams::set_print_special].
+    pub print_special: Option<bool>,
+    /// See [FullParams::set_print_progress].
+    pub print_progress: Option<bool>,
+    /// See [FullParams::set_print_realtime].
+    pub print_realtime: Option<bool>,
+    /// See [FullParams::set_print_timestamps].
+    pub print_timestamps: Option<bool>,
+    /// See [FullParams::set_token_timestamps].
+    pub token_timestamps: Option<bool>,
+    /// See [FullParams::set_thold_pt].
+    pub thold_pt: Option<f32>,
+    /// See [FullParams::set_thold_ptsum].
+    pub thold_ptsum: Option<f32>,
+    /// See [FullParams::set_max_len].
+    pub max_len: Option<c_int>,
+    /// See [FullParams::set_max_tokens].
+    pub max_tokens: Option<c_int>,
+    /// See [FullParams::set_speed_up].
+    pub speed_up: Option<bool>,
+    /// See [FullParams::set_audio_ctx].
+    pub audio_ctx: Option<c_int>,
+    /// See [FullParams::set_suppress_blank].
+    pub suppress_blank: Option<bool>,
+    /// See [FullParams::set_temperature].
+    pub temperature: Option<f32>,
+    /// See [FullParams::set_max_initial_ts].
+    pub max_initial_ts: Option<f32>,
+    /// See [FullParams::set_length_penalty].
+    pub length_penalty: Option<f32>,
+    /// See [FullParams::set_temperature_inc].
+    pub temperature_inc: Option<f32>,
+    /// See [FullParams::set_entropy_thold].
+    pub entropy_thold: Option<f32>,
+    /// See [FullParams::set_logprob_thold].
+    pub logprob_thold: Option<f32>,
+    /// See [FullParams::set_no_speech_thold].
+    pub no_speech_thold: Option<f32>,
+}
+
+impl TranscribeOptions {
+    /// Convert these options into [FullParams], validating them with [FullParamsBuilder::build].
+    ///
+    /// # Returns
+    /// Ok(FullParams) on success, Err(WhisperError) on failure.
+    pub fn to_full_params(&self) -> Result<FullParams, WhisperError> {
+        let mut builder = FullParamsBuilder::new(self.sampling_strategy);
+        if let Some(language) = &self.language {
+            builder = builder.language(Some(language));
+        }
+        if let Some(initial_prompt) = &self.initial_prompt {
+            builder = builder.initial_prompt(Some(initial_prompt));
+        }
+        if let Some(n_threads) = self.n_threads {
+            builder = builder.n_threads(n_threads);
+        }
+        if let Some(n_max_text_ctx) = self.n_max_text_ctx {
+            builder = builder.n_max_text_ctx(n_max_text_ctx);
+        }
+        if let Some(offset_ms) = self.offset_ms {
+            builder = builder.offset_ms(offset_ms);
+        }
+        if let Some(duration_ms) = self.duration_ms {
+            builder = builder.duration_ms(duration_ms);
+        }
+        if let Some(translate) = self.translate {
+            builder = builder.translate(translate);
+        }
+        if let Some(no_context) = self.no_context {
+            builder = builder.no_context(no_context);
+        }
+        if let Some(single_segment) = self.single_segment {
+            builder = builder.single_segment(single_segment);
+        }
+        if let Some(console_output) = self.console_output {
+            builder = builder.console_output(console_output);
+        }
+        if let Some(print_special) = self.print_special {
+            builder = builder.print_special(print_special);
+        }
+        if let Some(print_progress) = self.print_progress {
+            builder = builder.print_progress(print_progress);
+        }
+        if let Some(print_realtime) = self.print_realtime {
+            builder = builder.print_realtime(print_realtime);
+        }
+        if let Some(print_timestamps) = self.print_timestamps {
+            builder = builder.print_timestamps(print_timestamps);
+        }
+        if let Some(token_timestamps) = self.token_timestamps {
+            builder = builder.token_timestamps(token_timestamps);
+        }
+        if let Some(thold_pt) = self.thold_pt {
+            builder = builder.thold_pt(thold_pt);
+        }
+        if let Some(thold_ptsum) = self.thold_ptsum {
+            builder = builder.thold_ptsum(thold_ptsum);
+        }
+        if let Some(max_len) = self.max_len {
+            builder = builder.max_len(max_len);
+        }
+        if let Some(max_tokens) = self.max_tokens {
+            builder = builder.max_tokens(max_tokens);
+        }
+        if let Some(speed_up) = self.speed_up {
+            builder = builder.speed_up(speed_up);
+        }
+        if let Some(audio_ctx) = self.audio_ctx {
+            builder = builder.audio_ctx(audio_ctx);
+        }
+        if let Some(suppress_blank) = self.suppress_blank {
+            builder = builder.suppress_blank(suppress_blank);
+        }
+        if let Some(temperature) = self.temperature {
+            builder = builder.temperature(temperature);
+        }
+        if let Some(max_initial_ts) = self.max_initial_ts {
+            builder = builder.max_initial_ts(max_initial_ts);
+        }
+        if let Some(length_penalty) = self.length_penalty {
+            builder = builder.length_penalty(length_penalty);
+        }
+        if let Some(temperature_inc) = self.temperature_inc {
+            builder = builder.temperature_inc(temperature_inc);
+        }
+        if let Some(entropy_thold) = self.entropy_thold {
+            builder = builder.entropy_thold(entropy_thold);
+        }
+        if let Some(logprob_thold) = self.logprob_thold {
+            builder = builder.logprob_thold(logprob_thold);
+        }
+        if let Some(no_speech_thold) = self.no_speech_thold {
+            builder = builder.no_speech_thold(no_speech_thold);
+        }
+        builder.build()
+    }
+}
+
+impl TryFrom<&TranscribeOptions> for FullParams {
+    type Error = WhisperError;
+
+    fn try_from(options: &TranscribeOptions) -> Result<Self, Self::Error> {
+        options.to_full_params()
+    }
+}
+
+#[cfg(feature = "serde")]
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn assert_options_from_json() {
+        let options: TranscribeOptions = serde_json::from_str(
+            r#"{
+                "sampling_strategy": { "type": "beam_search", "beam_size": 5, "patience": -1.0 },
+                "language": "de",
+                "n_threads": 8,
+                "console_output": "none"
+            }"#,
+        )
+        .expect("valid options");
+        assert_eq!(
+            options,
+            TranscribeOptions {
+                sampling_strategy: SamplingStrategy::BeamSearch {
+                    beam_size: 5,
+                    patience: -1.0
+                },
+                language: Some("de".to_string()),
+                n_threads: Some(8),
+                console_output: Some(ConsoleOutput::None),
+                ..Default::default()
+            }
+        );
+    }
+}
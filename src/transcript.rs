@@ -0,0 +1,1083 @@
+//! Owned transcription results that do not borrow from a [WhisperContext](crate::WhisperContext).
+
+use crate::{Language, Task, Timestamp, WhisperToken};
+#[cfg(feature = "compression-ratio")]
+use flate2::{write::ZlibEncoder, Compression};
+use std::collections::HashMap;
+#[cfg(feature = "compression-ratio")]
+use std::io::Write;
+use std::time::Duration;
+
+/// A single token of a transcribed segment.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Token {
+    /// ID of the token.
+    pub id: WhisperToken,
+    /// Is this a special token, such as a timestamp or the end of text, rather than part of the text?
+    pub special: bool,
+    /// Text of the token.
+    ///
+    /// A token may only hold part of a multi-byte character. Such a character is part of the text of
+    /// the token that completes it, so joining the text of consecutive tokens gives the exact text.
+    pub text: String,
+    /// Probability of the token.
+    pub p: f32,
+    /// Log probability of the token.
+    pub plog: f32,
+    /// Start time of the token, in centiseconds. Only meaningful with token timestamps enabled.
+    pub t0: i64,
+    /// End time of the token, in centiseconds. Only meaningful with token timestamps enabled.
+    pub t1: i64,
+}
+
+/// A single transcribed segment.
+///
+/// The bundled whisper.cpp does not report which temperature a segment was decoded at,
+/// but the figures it uses to decide whether to retry at a higher one can be recomputed with
+/// [Segment::avg_logprob], [Segment::entropy] and [Segment::compression_ratio].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Segment {
+    /// Text of the segment.
+    pub text: String,
+    /// Start time of the segment, in centiseconds.
+    pub t0: i64,
+    /// End time of the segment, in centiseconds.
+    pub t1: i64,
+    /// Tokens that make up the segment.
+    pub tokens: Vec<Token>,
+}
+
+impl Segment {
+    /// How confident the model is in the text of this segment, between 0.0 and 1.0.
+    ///
+    /// This is the [ConfidenceStrategy::GeometricMean] of the probabilities of its text tokens.
+    ///
+    /// # Returns
+    /// The confidence, None if the segment has no text tokens.
+    pub fn confidence(&self) -> Option<f32> {
+        self.confidence_with(ConfidenceStrategy::default())
+    }
+
+    /// How confident the model is in the text of this segment, computed with the given strategy.
+    ///
+    /// # Returns
+    /// The confidence, None if the segment has no text tokens.
+    pub fn confidence_with(&self, strategy: ConfidenceStrategy) -> Option<f32> {
+        strategy.aggregate(&self.tokens)
+    }
+
+    /// Mean log probability of the text tokens of this segment.
+    ///
+    /// whisper.cpp decodes a window again at a higher temperature if this is below
+    /// [FullParams::set_logprob_thold](crate::FullParams::set_logprob_thold).
+    ///
+    /// # Returns
+    /// The mean log probability, None if the segment has no text tokens.
+    pub fn avg_logprob(&self) -> Option<f32> {
+        let (n, sum) = self
+            .tokens
+            .iter()
+            .filter(|token| !token.special)
+            .fold((0, 0.0), |(n, sum), token| (n + 1, sum + token.plog));
+        (n > 0).then(|| sum / n as f32)
+    }
+
+    /// Entropy of the IDs of the last 32 tokens of this segment, computed the same way as whisper.cpp.
+    ///
+    /// Low values mean the same few tokens are repeated. whisper.cpp decodes a window again at a higher temperature
+    /// if this is below [FullParams::set_entropy_thold](crate::FullParams::set_entropy_thold),
+    /// though it only checks sequences that are longer than 32 tokens.
+    pub fn entropy(&self) -> f32 {
+        let last = &self.tokens[self.tokens.len().saturating_sub(32)..];
+        let mut counts: HashMap<WhisperToken, usize> = HashMap::new();
+        for token in last {
+            *counts.entry(token.id).or_default() += 1;
+        }
+        counts
+            .values()
+            .map(|&count| {
+                let p = count as f64 / last.len() as f64;
+                -p * p.ln()
+            })
+            .sum::<f64>() as f32
+    }
+
+    /// Ratio of the size of the text of this segment to its zlib-compressed size,
+    /// the same measure of repetitiveness as OpenAI's reference implementation uses.
+    ///
+    /// Values above 2.4 usually mean the text is stuck in a loop.
+    ///
+    /// Only available with the `compression-ratio` feature.
+    ///
+    /// # Returns
+    /// The compression ratio, 0.0 if the segment has no text.
+    #[cfg(feature = "compression-ratio")]
+    pub fn compression_ratio(&self) -> f32 {
+        if self.text.is_empty() {
+            return 0.0;
+        }
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        // writing to a Vec can't fail
+        encoder.write_all(self.text.as_bytes()).unwrap();
+        let compressed = encoder.finish().unwrap();
+        self.text.len() as f32 / compressed.len() as f32
+    }
+
+    /// Split the segment into words.
+    ///
+    /// A word starts at every text token that begins with a space. Special tokens are left out.
+    /// Word boundaries are only as good as the tokenizer's, so punctuation is attached to the word before it.
+    pub fn words(&self) -> Vec<Word> {
+        let mut words: Vec<Word> = Vec::new();
+        for token in self.tokens.iter().filter(|token| !token.special) {
+            match words.last_mut() {
+                Some(word) if !token.text.starts_with(' ') => {
+                    word.text.push_str(&token.text);
+                    word.t1 = token.t1;
+                    word.tokens.push(token.clone());
+                }
+                _ => words.push(Word {
+                    text: token.text.trim_start().to_string(),
+                    t0: token.t0,
+                    t1: token.t1,
+                    tokens: vec![token.clone()],
+                }),
+            }
+        }
+        words
+    }
+
+    /// Start time of the segment.
+    #[inline]
+    pub fn start(&self) -> Timestamp {
+        Timestamp::from_centiseconds(self.t0)
+    }
+
+    /// End time of the segment.
+    #[inline]
+    pub fn end(&self) -> Timestamp {
+        Timestamp::from_centiseconds(self.t1)
+    }
+}
+
+/// A word of a [Segment], made up of one or more tokens.
+///
+/// Get these with [Segment::words].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Word {
+    /// Text of the word, without leading whitespace.
+    pub text: String,
+    /// Start time of the word, in centiseconds. Only meaningful with token timestamps enabled.
+    pub t0: i64,
+    /// End time of the word, in centiseconds. Only meaningful with token timestamps enabled.
+    pub t1: i64,
+    /// Tokens that make up the word.
+    pub tokens: Vec<Token>,
+}
+
+impl Word {
+    /// How confident the model is in this word, between 0.0 and 1.0.
+    ///
+    /// This is the [ConfidenceStrategy::GeometricMean] of the probabilities of its tokens.
+    ///
+    /// # Returns
+    /// The confidence, None if the word has no tokens.
+    pub fn confidence(&self) -> Option<f32> {
+        self.confidence_with(ConfidenceStrategy::default())
+    }
+
+    /// How confident the model is in this word, computed with the given strategy.
+    ///
+    /// # Returns
+    /// The confidence, None if the word has no tokens.
+    pub fn confidence_with(&self, strategy: ConfidenceStrategy) -> Option<f32> {
+        strategy.aggregate(&self.tokens)
+    }
+}
+
+/// How the probabilities of several tokens are combined into a single confidence score.
+///
+/// Special tokens are always left out.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum ConfidenceStrategy {
+    /// `exp(mean(plog))`, the geometric mean of the token probabilities.
+    ///
+    /// This is the per-token probability of the sequence as a whole,
+    /// so a single unlikely token lowers it more than it would the arithmetic mean.
+    #[default]
+    GeometricMean,
+    /// `mean(p)`, the arithmetic mean of the token probabilities.
+    ArithmeticMean,
+    /// `min(p)`, the probability of the least likely token.
+    Min,
+}
+
+impl ConfidenceStrategy {
+    fn aggregate(&self, tokens: &[Token]) -> Option<f32> {
+        let tokens = tokens.iter().filter(|token| !token.special);
+        let (n, total) = match self {
+            Self::GeometricMean => tokens.fold((0, 0.0), |(n, sum), t| (n + 1, sum + t.plog)),
+            Self::ArithmeticMean => tokens.fold((0, 0.0), |(n, sum), t| (n + 1, sum + t.p)),
+            Self::Min => tokens.fold((0, f32::INFINITY), |(n, min), t| (n + 1, min.min(t.p))),
+        };
+        if n == 0 {
+            return None;
+        }
+        Some(match self {
+            Self::GeometricMean => (total / n as f32).exp(),
+            Self::ArithmeticMean => total / n as f32,
+            Self::Min => total,
+        })
+    }
+}
+
+/// The full result of a transcription run.
+///
+/// Create this with [WhisperContext::collect_transcript](crate::WhisperContext::collect_transcript)
+/// after calling [WhisperContext::full](crate::WhisperContext::full).
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Transcript {
+    /// Segments of the transcript, in the order they were generated.
+    pub segments: Vec<Segment>,
+    /// The task the model performed.
+    pub task: Task,
+    /// The language the model was told the speech is in.
+    ///
+    /// None if the language was auto-detected, as the bundled whisper.cpp does not report which one it picked.
+    /// Use [WhisperContext::lang_detect](crate::WhisperContext::lang_detect) to detect the language beforehand instead.
+    pub language: Option<Language>,
+    /// Duration of the transcribed audio, see [WhisperContext::full_processed_duration](crate::WhisperContext::full_processed_duration).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub duration: Duration,
+}
+
+impl Transcript {
+    /// Concatenate the text of all segments.
+    pub fn text(&self) -> String {
+        self.segments.iter().map(|s| s.text.as_str()).collect()
+    }
+
+    /// Render the transcript as SRT subtitles, with one cue per segment.
+    pub fn to_srt(&self) -> String {
+        let mut srt = String::new();
+        for (i, segment) in self.segments.iter().enumerate() {
+            srt.push_str(&srt_cue(i, segment));
+        }
+        srt
+    }
+
+    /// Render the transcript as WebVTT subtitles, with one cue per segment.
+    pub fn to_vtt(&self) -> String {
+        let mut vtt = String::from("WEBVTT\n\n");
+        for segment in &self.segments {
+            vtt.push_str(&format!(
+                "{} --> {}\n{}\n\n",
+                segment.start().to_vtt(),
+                segment.end().to_vtt(),
+                segment.text.trim()
+            ));
+        }
+        vtt
+    }
+
+    /// Render the transcript as CSV, with a header and a row per segment.
+    ///
+    /// The columns are the start and end time in milliseconds, the [Segment::confidence] (empty if it has none),
+    /// and the text, which is always quoted.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("start_ms,end_ms,confidence,text\n");
+        for segment in &self.segments {
+            csv.push_str(&format!(
+                "{},{},{},\"{}\"\n",
+                segment.start().as_millis(),
+                segment.end().as_millis(),
+                format_confidence(segment),
+                segment.text.trim().replace('"', "\"\"")
+            ));
+        }
+        csv
+    }
+
+    /// Render the transcript as TSV, with the same header and columns as [Transcript::to_csv].
+    ///
+    /// TSV has no quoting, so tabs and line breaks in the text are replaced with spaces.
+    pub fn to_tsv(&self) -> String {
+        let mut tsv = String::from("start_ms\tend_ms\tconfidence\ttext\n");
+        for segment in &self.segments {
+            tsv.push_str(&format!(
+                "{}\t{}\t{}\t{}\n",
+                segment.start().as_millis(),
+                segment.end().as_millis(),
+                format_confidence(segment),
+                segment.text.trim().replace(['\t', '\r', '\n'], " ")
+            ));
+        }
+        tsv
+    }
+
+    /// Render the transcript as enhanced LRC lyrics, with one line per segment and the start of every word marked.
+    ///
+    /// Enable [FullParams::set_token_timestamps](crate::FullParams::set_token_timestamps)
+    /// for the words to have meaningful times. This applies to all word-timed formats.
+    pub fn to_lrc(&self) -> String {
+        let mut lrc = String::new();
+        for segment in &self.segments {
+            lrc.push_str(&format!("[{}]", segment.start().to_lrc()));
+            let words = segment.words();
+            if words.is_empty() {
+                lrc.push_str(segment.text.trim());
+            }
+            for (i, word) in words.iter().enumerate() {
+                if i > 0 {
+                    lrc.push(' ');
+                }
+                let start = Timestamp::from_centiseconds(word.t0);
+                lrc.push_str(&format!("<{}>{}", start.to_lrc(), word.text));
+            }
+            lrc.push_str(&format!("<{}>\n", segment.end().to_lrc()));
+        }
+        lrc
+    }
+
+    /// Render the transcript as TTML, with a paragraph per segment and a span per word.
+    pub fn to_ttml(&self) -> String {
+        let mut ttml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        match self.language {
+            Some(language) => ttml.push_str(&format!(
+                "<tt xmlns=\"http://www.w3.org/ns/ttml\" xml:lang=\"{}\">\n",
+                language.code()
+            )),
+            None => ttml.push_str("<tt xmlns=\"http://www.w3.org/ns/ttml\">\n"),
+        }
+        ttml.push_str("  <body>\n    <div>\n");
+        for segment in &self.segments {
+            ttml.push_str(&format!(
+                "      <p begin=\"{}\" end=\"{}\">",
+                segment.start().to_vtt(),
+                segment.end().to_vtt()
+            ));
+            let words = segment.words();
+            if words.is_empty() {
+                ttml.push_str(&escape_xml(segment.text.trim()));
+            }
+            for (i, word) in words.iter().enumerate() {
+                if i > 0 {
+                    ttml.push(' ');
+                }
+                ttml.push_str(&format!(
+                    "<span begin=\"{}\" end=\"{}\">{}</span>",
+                    Timestamp::from_centiseconds(word.t0).to_vtt(),
+                    Timestamp::from_centiseconds(word.t1).to_vtt(),
+                    escape_xml(&word.text)
+                ));
+            }
+            ttml.push_str("</p>\n");
+        }
+        ttml.push_str("    </div>\n  </body>\n</tt>\n");
+        ttml
+    }
+
+    /// Render the transcript as ASS subtitles for karaoke, with one line per segment
+    /// and a `\k` tag highlighting every word until the next one starts.
+    pub fn to_ass(&self) -> String {
+        let mut ass = String::from(ASS_HEADER);
+        for segment in &self.segments {
+            ass.push_str(&format!(
+                "Dialogue: 0,{},{},Default,,0,0,0,,",
+                segment.start().to_ass(),
+                segment.end().to_ass()
+            ));
+            let words = segment.words();
+            if words.is_empty() {
+                ass.push_str(&format!(
+                    "{{\\k{}}}{}",
+                    (segment.t1 - segment.t0).max(0),
+                    escape_ass(segment.text.trim())
+                ));
+            }
+            let mut cursor = segment.t0;
+            for (i, word) in words.iter().enumerate() {
+                if word.t0 > cursor {
+                    ass.push_str(&format!("{{\\k{}}}", word.t0 - cursor));
+                    cursor = word.t0;
+                }
+                let next = words.get(i + 1).map_or(segment.t1, |next| next.t0);
+                let duration = (next - cursor).max(0);
+                cursor += duration;
+                let space = if i + 1 < words.len() { " " } else { "" };
+                ass.push_str(&format!(
+                    "{{\\k{}}}{}{}",
+                    duration,
+                    escape_ass(&word.text),
+                    space
+                ));
+            }
+            ass.push('\n');
+        }
+        ass
+    }
+
+    /// Split segments longer than `max_len` characters, only ever between words.
+    ///
+    /// The bundled whisper.cpp has no `split_on_word` parameter, so [FullParams::set_max_len](crate::FullParams::set_max_len)
+    /// may split in the middle of a word. Run without it and split afterwards with this instead.
+    /// The new segments get the times of their first and last word, so enable
+    /// [FullParams::set_token_timestamps](crate::FullParams::set_token_timestamps) for them to be meaningful.
+    ///
+    /// A single word longer than `max_len` becomes a segment of its own, and special tokens are left out of split segments.
+    /// Segments whose text isn't made up of their text tokens are kept as they are, as there is nothing to split them by.
+    /// This includes segments without tokens, and segments transcribed with
+    /// [FullParams::set_print_special](crate::FullParams::set_print_special), whose text includes the special tokens.
+    ///
+    /// # Arguments
+    /// * max_len: Maximum length of the text of a segment in characters, without leading and trailing whitespace.
+    pub fn split_on_words(&mut self, max_len: usize) {
+        let mut segments = Vec::with_capacity(self.segments.len());
+        for segment in self.segments.drain(..) {
+            let text_tokens = segment.tokens.iter().filter(|token| !token.special);
+            if segment.text.trim().chars().count() <= max_len
+                || text_tokens
+                    .map(|token| token.text.as_str())
+                    .collect::<String>()
+                    != segment.text
+            {
+                segments.push(segment);
+                continue;
+            }
+            let mut current: Option<(Segment, usize)> = None;
+            for word in segment.words() {
+                let word_len = word.text.chars().count();
+                match &mut current {
+                    Some((split, len)) if *len + 1 + word_len <= max_len => {
+                        *len += 1 + word_len;
+                        split.t1 = word.t1;
+                        split
+                            .text
+                            .extend(word.tokens.iter().map(|token| token.text.as_str()));
+                        split.tokens.extend(word.tokens);
+                    }
+                    _ => {
+                        segments.extend(current.take().map(|(split, _)| split));
+                        let split = Segment {
+                            text: word
+                                .tokens
+                                .iter()
+                                .map(|token| token.text.as_str())
+                                .collect(),
+                            t0: word.t0,
+                            t1: word.t1,
+                            tokens: word.tokens,
+                        };
+                        current = Some((split, word_len));
+                    }
+                }
+            }
+            segments.extend(current.map(|(split, _)| split));
+        }
+        self.segments = segments;
+    }
+
+    /// Are there no segments in this transcript?
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// Iterate over the segments in parallel, e.g. to post-process hours of audio.
+    ///
+    /// Only available with the `rayon` feature.
+    #[cfg(feature = "rayon")]
+    pub fn par_segments(&self) -> rayon::slice::Iter<'_, Segment> {
+        use rayon::prelude::*;
+        self.segments.par_iter()
+    }
+
+    /// Iterate over the segments in parallel, allowing them to be modified.
+    ///
+    /// Only available with the `rayon` feature.
+    #[cfg(feature = "rayon")]
+    pub fn par_segments_mut(&mut self) -> rayon::slice::IterMut<'_, Segment> {
+        use rayon::prelude::*;
+        self.segments.par_iter_mut()
+    }
+
+    /// Split every segment into words in parallel, like [Segment::words].
+    ///
+    /// Only available with the `rayon` feature.
+    ///
+    /// # Returns
+    /// The words of each segment, in the order of the segments.
+    #[cfg(feature = "rayon")]
+    pub fn par_words(&self) -> Vec<Vec<Word>> {
+        use rayon::prelude::*;
+        self.segments.par_iter().map(Segment::words).collect()
+    }
+
+    /// Compute the confidence of every segment in parallel, like [Segment::confidence_with].
+    ///
+    /// Only available with the `rayon` feature.
+    ///
+    /// # Returns
+    /// The confidence of each segment, in the order of the segments.
+    #[cfg(feature = "rayon")]
+    pub fn par_confidence_with(&self, strategy: ConfidenceStrategy) -> Vec<Option<f32>> {
+        use rayon::prelude::*;
+        self.segments
+            .par_iter()
+            .map(|segment| segment.confidence_with(strategy))
+            .collect()
+    }
+
+    /// Render the transcript as SRT subtitles like [Transcript::to_srt], formatting the cues in parallel.
+    ///
+    /// Only available with the `rayon` feature.
+    #[cfg(feature = "rayon")]
+    pub fn par_to_srt(&self) -> String {
+        use rayon::prelude::*;
+        self.segments
+            .par_iter()
+            .enumerate()
+            .map(|(i, segment)| srt_cue(i, segment))
+            .collect()
+    }
+}
+
+/// The SRT cue for the segment at index `i`.
+fn srt_cue(i: usize, segment: &Segment) -> String {
+    format!(
+        "{}\n{} --> {}\n{}\n\n",
+        i + 1,
+        segment.start().to_srt(),
+        segment.end().to_srt(),
+        segment.text.trim()
+    )
+}
+
+/// Start of every ASS file rendered by [Transcript::to_ass], with a single style
+/// that shows words in white once they are highlighted and in red before.
+const ASS_HEADER: &str = "[Script Info]
+ScriptType: v4.00+
+PlayResX: 384
+PlayResY: 288
+
+[V4+ Styles]
+Format: Name, Fontname, Fontsize, PrimaryColour, SecondaryColour, OutlineColour, BackColour, \
+Bold, Italic, Underline, StrikeOut, ScaleX, ScaleY, Spacing, Angle, BorderStyle, Outline, Shadow, \
+Alignment, MarginL, MarginR, MarginV, Encoding
+Style: Default,Arial,20,&H00FFFFFF,&H000000FF,&H00000000,&H00000000,0,0,0,0,100,100,0,0,1,2,0,2,10,10,10,1
+
+[Events]
+Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+";
+
+fn format_confidence(segment: &Segment) -> String {
+    segment
+        .confidence()
+        .map(|confidence| format!("{:.3}", confidence))
+        .unwrap_or_default()
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// ASS has no way to escape braces, which start override tags, so they are replaced.
+fn escape_ass(text: &str) -> String {
+    text.replace('{', "(")
+        .replace('}', ")")
+        .replace('\\', "/")
+        .replace('\n', "\\N")
+}
+
+/// Shortest run of tokens [merge] accepts as the overlap of two transcripts,
+/// so that a single common word doesn't cause a wrong join.
+const MIN_MERGE_TOKENS: usize = 2;
+
+/// Join the transcripts of two overlapping chunks of audio, without repeating the text they share.
+///
+/// The join is made at the longest run of text tokens that is transcribed in both overlapping ends.
+/// Everything up to and including that run is kept from `a`, everything after it is kept from `b`,
+/// and the two segments it is found in are combined into one.
+/// If no such run is found, the overlap is cut in the middle: segments of `a` starting before that point are kept,
+/// and so are segments of `b` starting after it.
+///
+/// The text of the combined segment is rebuilt from its tokens,
+/// so it is subject to the same limitations as [Token::text].
+///
+/// # Arguments
+/// * a: The transcript of the earlier chunk. This can be the result of a previous merge.
+/// * b: The transcript of the later chunk, with times relative to the start of that chunk.
+/// * offset: Where the later chunk starts, relative to the times of `a`.
+/// * overlap: How long the two chunks overlap.
+///
+/// # Returns
+/// The merged transcript, with the times of `a`, and its task and language.
+/// Its duration runs up to the end of whichever transcript ends later.
+pub fn merge(a: Transcript, mut b: Transcript, offset: Duration, overlap: Duration) -> Transcript {
+    let duration = a.duration.max(offset + b.duration);
+    let offset = (offset.as_millis() / 10) as i64;
+    let overlap_end = offset + (overlap.as_millis() / 10) as i64;
+    for segment in &mut b.segments {
+        segment.t0 += offset;
+        segment.t1 += offset;
+        for token in &mut segment.tokens {
+            token.t0 += offset;
+            token.t1 += offset;
+        }
+    }
+
+    let a_tail = text_tokens(&a.segments, |segment| segment.t1 > offset);
+    let b_head = text_tokens(&b.segments, |segment| segment.t0 < overlap_end);
+    let mut segments = a.segments;
+    match longest_common_run(&a_tail, &b_head) {
+        Some((a_end, b_end)) => {
+            let (a_segment, a_token, _) = a_tail[a_end];
+            let (b_segment, b_token, _) = b_head[b_end];
+            segments.truncate(a_segment + 1);
+            let mut rest = b.segments.into_iter().skip(b_segment);
+            // both indices come from tokens of existing segments
+            let joined = segments.last_mut().unwrap();
+            let next = rest.next().unwrap();
+            joined.tokens.truncate(a_token + 1);
+            joined
+                .tokens
+                .extend(next.tokens.into_iter().skip(b_token + 1));
+            joined.t1 = next.t1;
+            joined.text = joined
+                .tokens
+                .iter()
+                .filter(|token| !token.special)
+                .map(|token| token.text.as_str())
+                .collect();
+            segments.extend(rest);
+        }
+        None => {
+            let cut = (offset + overlap_end) / 2;
+            segments.retain(|segment| segment.t0 < cut);
+            segments.extend(b.segments.into_iter().filter(|segment| segment.t0 >= cut));
+        }
+    }
+    Transcript {
+        segments,
+        task: a.task,
+        language: a.language,
+        duration,
+    }
+}
+
+/// Segment index, token index and ID of the text tokens of the segments matching `filter`.
+fn text_tokens(
+    segments: &[Segment],
+    filter: impl Fn(&Segment) -> bool,
+) -> Vec<(usize, usize, WhisperToken)> {
+    segments
+        .iter()
+        .enumerate()
+        .filter(|(_, segment)| filter(segment))
+        .flat_map(|(i, segment)| {
+            segment
+                .tokens
+                .iter()
+                .enumerate()
+                .filter(|(_, token)| !token.special)
+                .map(move |(j, token)| (i, j, token.id))
+        })
+        .collect()
+}
+
+/// Find the longest run of token IDs that appears in both `a` and `b`.
+///
+/// # Returns
+/// The index of the last token of the run in `a` and in `b`,
+/// None if there is no run of at least [MIN_MERGE_TOKENS] tokens.
+fn longest_common_run(
+    a: &[(usize, usize, WhisperToken)],
+    b: &[(usize, usize, WhisperToken)],
+) -> Option<(usize, usize)> {
+    // lengths of the common runs ending at the previous token of `a` and each token of `b`
+    let mut previous = vec![0; b.len() + 1];
+    let mut current = vec![0; b.len() + 1];
+    let mut best = (0, 0, 0);
+    for (i, &(_, _, a_id)) in a.iter().enumerate() {
+        for (j, &(_, _, b_id)) in b.iter().enumerate() {
+            current[j + 1] = if a_id == b_id { previous[j] + 1 } else { 0 };
+            if current[j + 1] > best.0 {
+                best = (current[j + 1], i, j);
+            }
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    let (len, a_end, b_end) = best;
+    (len >= MIN_MERGE_TOKENS).then_some((a_end, b_end))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn token(text: &str, p: f32, special: bool) -> Token {
+        Token {
+            id: 0,
+            special,
+            text: text.to_string(),
+            p,
+            plog: p.ln(),
+            t0: 0,
+            t1: 0,
+        }
+    }
+
+    #[test]
+    fn assert_subtitles() {
+        let transcript = Transcript {
+            segments: vec![
+                Segment {
+                    text: " Hello.".to_string(),
+                    t0: 0,
+                    t1: 150,
+                    tokens: vec![],
+                },
+                Segment {
+                    text: " World.".to_string(),
+                    t0: 150,
+                    t1: 6234,
+                    tokens: vec![],
+                },
+            ],
+            ..Default::default()
+        };
+        assert_eq!(
+            transcript.to_srt(),
+            "1\n00:00:00,000 --> 00:00:01,500\nHello.\n\n2\n00:00:01,500 --> 00:01:02,340\nWorld.\n\n"
+        );
+        assert_eq!(
+            transcript.to_vtt(),
+            "WEBVTT\n\n00:00:00.000 --> 00:00:01.500\nHello.\n\n00:00:01.500 --> 00:01:02.340\nWorld.\n\n"
+        );
+    }
+
+    #[test]
+    fn assert_words_and_confidence() {
+        let segment = Segment {
+            text: " Hello world.".to_string(),
+            t0: 0,
+            t1: 100,
+            tokens: vec![
+                token("[_BEG_]", 0.1, true),
+                token(" Hel", 0.5, false),
+                token("lo", 0.5, false),
+                token(" world", 1.0, false),
+                token(".", 1.0, false),
+            ],
+        };
+
+        let words = segment.words();
+        let texts: Vec<_> = words.iter().map(|word| word.text.as_str()).collect();
+        assert_eq!(texts, ["Hello", "world."]);
+        assert!((words[0].confidence().unwrap() - 0.5).abs() < 1e-6);
+        assert_eq!(words[1].confidence(), Some(1.0));
+
+        assert!((segment.confidence().unwrap() - 0.5f32.sqrt()).abs() < 1e-6);
+        assert_eq!(
+            segment.confidence_with(ConfidenceStrategy::ArithmeticMean),
+            Some(0.75)
+        );
+        assert_eq!(segment.confidence_with(ConfidenceStrategy::Min), Some(0.5));
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn assert_parallel_matches_sequential() {
+        let transcript = Transcript {
+            segments: (0..100)
+                .map(|i| Segment {
+                    text: format!(" Segment {}.", i),
+                    t0: i * 100,
+                    t1: i * 100 + 100,
+                    tokens: vec![
+                        token(" Segment", 0.5, false),
+                        token(&format!(" {}.", i), 1.0, false),
+                    ],
+                })
+                .collect(),
+            ..Default::default()
+        };
+        assert_eq!(transcript.par_to_srt(), transcript.to_srt());
+        let words: Vec<_> = transcript.segments.iter().map(Segment::words).collect();
+        assert_eq!(transcript.par_words(), words);
+        let strategy = ConfidenceStrategy::Min;
+        let confidence: Vec<_> = transcript
+            .segments
+            .iter()
+            .map(|segment| segment.confidence_with(strategy))
+            .collect();
+        assert_eq!(transcript.par_confidence_with(strategy), confidence);
+    }
+
+    #[test]
+    fn assert_decoding_stats() {
+        let looping = Segment {
+            text: " and then".repeat(20),
+            t0: 0,
+            t1: 100,
+            tokens: (0..40)
+                .map(|i| Token {
+                    id: i % 2,
+                    ..token("", 0.5, false)
+                })
+                .collect(),
+        };
+        assert!((looping.entropy() - 2f32.ln()).abs() < 1e-6);
+        #[cfg(feature = "compression-ratio")]
+        assert!(looping.compression_ratio() > 2.4);
+        assert!((looping.avg_logprob().unwrap() - 0.5f32.ln()).abs() < 1e-6);
+
+        let empty = Segment {
+            text: String::new(),
+            t0: 0,
+            t1: 0,
+            tokens: vec![token("[_BEG_]", 1.0, true)],
+        };
+        assert_eq!(empty.entropy(), 0.0);
+        #[cfg(feature = "compression-ratio")]
+        assert_eq!(empty.compression_ratio(), 0.0);
+        assert_eq!(empty.avg_logprob(), None);
+    }
+
+    #[test]
+    fn assert_merge_on_common_tokens() {
+        let segment = |text: &str, t0, t1, ids: &[(WhisperToken, &str)]| Segment {
+            text: text.to_string(),
+            t0,
+            t1,
+            tokens: ids
+                .iter()
+                .map(|&(id, text)| Token {
+                    id,
+                    special: id >= 100,
+                    ..token(text, 1.0, false)
+                })
+                .collect(),
+        };
+        let a = Transcript {
+            segments: vec![
+                segment(" Hi.", 0, 100, &[(100, ""), (1, " Hi."), (101, "")]),
+                segment(
+                    " The quick brown fox",
+                    100,
+                    500,
+                    &[(2, " The"), (3, " quick"), (4, " brown"), (5, " fox")],
+                ),
+            ],
+            ..Default::default()
+        };
+        let b = Transcript {
+            segments: vec![
+                segment(
+                    " brown fox jumps.",
+                    0,
+                    300,
+                    &[
+                        (100, ""),
+                        (4, " brown"),
+                        (5, " fox"),
+                        (6, " jumps."),
+                        (102, ""),
+                    ],
+                ),
+                segment(" Bye.", 300, 400, &[(7, " Bye.")]),
+            ],
+            ..Default::default()
+        };
+
+        let merged = merge(a, b, Duration::from_secs(4), Duration::from_secs(1));
+        let texts: Vec<_> = merged.segments.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(texts, [" Hi.", " The quick brown fox jumps.", " Bye."]);
+        assert_eq!((merged.segments[1].t0, merged.segments[1].t1), (100, 700));
+        assert_eq!(merged.segments[1].tokens.len(), 6);
+        assert_eq!((merged.segments[2].t0, merged.segments[2].t1), (700, 800));
+    }
+
+    #[test]
+    fn assert_merge_without_common_tokens() {
+        let segment = |t0, t1| Segment {
+            text: String::new(),
+            t0,
+            t1,
+            tokens: vec![],
+        };
+        let a = Transcript {
+            segments: vec![segment(0, 420), segment(455, 480)],
+            ..Default::default()
+        };
+        let b = Transcript {
+            segments: vec![segment(0, 20), segment(60, 200)],
+            ..Default::default()
+        };
+
+        let merged = merge(a, b, Duration::from_secs(4), Duration::from_secs(1));
+        let times: Vec<_> = merged.segments.iter().map(|s| (s.t0, s.t1)).collect();
+        assert_eq!(times, [(0, 420), (460, 600)]);
+    }
+
+    #[test]
+    fn assert_word_timed_formats() {
+        let timed = |text: &str, t0, t1| Token {
+            t0,
+            t1,
+            ..token(text, 1.0, false)
+        };
+        let transcript = Transcript {
+            segments: vec![Segment {
+                text: " Rock & roll".to_string(),
+                t0: 100,
+                t1: 300,
+                tokens: vec![
+                    Token {
+                        t0: 100,
+                        t1: 100,
+                        ..token("[_BEG_]", 1.0, true)
+                    },
+                    timed(" Rock", 110, 150),
+                    timed(" &", 150, 160),
+                    timed(" roll", 170, 250),
+                ],
+            }],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            transcript.to_lrc(),
+            "[00:01.00]<00:01.10>Rock <00:01.50>& <00:01.70>roll<00:03.00>\n"
+        );
+        assert!(transcript.to_ttml().contains(
+            "<p begin=\"00:00:01.000\" end=\"00:00:03.000\">\
+             <span begin=\"00:00:01.100\" end=\"00:00:01.500\">Rock</span> \
+             <span begin=\"00:00:01.500\" end=\"00:00:01.600\">&amp;</span> \
+             <span begin=\"00:00:01.700\" end=\"00:00:02.500\">roll</span></p>"
+        ));
+        assert!(transcript.to_ass().ends_with(
+            "Dialogue: 0,0:00:01.00,0:00:03.00,Default,,0,0,0,,{\\k10}{\\k40}Rock {\\k20}& {\\k130}roll\n"
+        ));
+    }
+
+    #[test]
+    fn assert_tables() {
+        let transcript = Transcript {
+            segments: vec![
+                Segment {
+                    text: " He said \"hi\", twice.".to_string(),
+                    t0: 0,
+                    t1: 150,
+                    tokens: vec![token(" He", 0.5, false), token(" said", 0.5, false)],
+                },
+                Segment {
+                    text: " Tab\there.".to_string(),
+                    t0: 150,
+                    t1: 200,
+                    tokens: vec![],
+                },
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            transcript.to_csv(),
+            "start_ms,end_ms,confidence,text\n\
+             0,1500,0.500,\"He said \"\"hi\"\", twice.\"\n\
+             1500,2000,,\"Tab\there.\"\n"
+        );
+        assert_eq!(
+            transcript.to_tsv(),
+            "start_ms\tend_ms\tconfidence\ttext\n\
+             0\t1500\t0.500\tHe said \"hi\", twice.\n\
+             1500\t2000\t\tTab here.\n"
+        );
+    }
+
+    #[test]
+    fn assert_split_on_words() {
+        let timed = |text: &str, t0, t1| Token {
+            t0,
+            t1,
+            ..token(text, 1.0, false)
+        };
+        let mut transcript = Transcript {
+            segments: vec![
+                Segment {
+                    text: " The quick brown fox.".to_string(),
+                    t0: 0,
+                    t1: 400,
+                    tokens: vec![
+                        token("[_BEG_]", 1.0, true),
+                        timed(" The", 0, 50),
+                        timed(" qu", 50, 100),
+                        timed("ick", 100, 150),
+                        timed(" brown", 150, 300),
+                        timed(" fox", 300, 380),
+                        timed(".", 380, 400),
+                    ],
+                },
+                Segment {
+                    text: " Short.".to_string(),
+                    t0: 400,
+                    t1: 500,
+                    tokens: vec![],
+                },
+            ],
+            ..Default::default()
+        };
+        transcript.split_on_words(10);
+
+        let split: Vec<_> = transcript
+            .segments
+            .iter()
+            .map(|segment| (segment.text.as_str(), segment.t0, segment.t1))
+            .collect();
+        assert_eq!(
+            split,
+            [
+                (" The quick", 0, 150),
+                (" brown fox.", 150, 400),
+                (" Short.", 400, 500)
+            ]
+        );
+        assert_eq!(transcript.segments[0].tokens.len(), 3);
+
+        // nothing to split these by, so they must be kept
+        let untokenized = Segment {
+            text: " A long segment without tokens.".to_string(),
+            t0: 0,
+            t1: 300,
+            tokens: vec![],
+        };
+        let special = Segment {
+            text: "[_BEG_] Hello there.".to_string(),
+            tokens: vec![
+                token("[_BEG_]", 1.0, true),
+                token(" Hello", 1.0, false),
+                token(" there.", 1.0, false),
+            ],
+            ..untokenized.clone()
+        };
+        let mut transcript = Transcript {
+            segments: vec![untokenized, special],
+            ..Default::default()
+        };
+        let expected = transcript.segments.clone();
+        transcript.split_on_words(10);
+        assert_eq!(transcript.segments, expected);
+    }
+}
@@ -0,0 +1,199 @@
+//! A lightweight voice activity detector, for skipping silence before running the model.
+//!
+//! Whisper tends to hallucinate text on long stretches of silence,
+//! so transcribing only the parts of the audio that contain speech improves both accuracy and speed.
+//!
+//! Audio is split into short frames, which count as speech if they are loud enough
+//! and do not cross zero so often that they are more likely to be noise.
+//!
+//! ```
+//! use whisper_rs::vad::{detect_speech, VadConfig};
+//!
+//! let audio = vec![0.0; 16000];
+//! for range in detect_speech(&audio, &VadConfig::default()) {
+//!     let speech = &audio[range];
+//!     // run the model on `speech`
+//! }
+//! ```
+
+use std::ops::Range;
+
+/// Sample rate of the audio given to whisper.cpp.
+const SAMPLE_RATE: usize = whisper_rs_sys::WHISPER_SAMPLE_RATE as usize;
+
+/// Settings for [detect_speech].
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct VadConfig {
+    /// Length of the frames the audio is split into, in milliseconds.
+    ///
+    /// Defaults to 30.
+    pub frame_ms: u32,
+    /// Root mean square a frame must reach to count as speech, for samples between -1.0 and 1.0.
+    ///
+    /// Defaults to 0.01.
+    pub energy_threshold: f32,
+    /// Fraction of consecutive samples that may change sign in a frame that counts as speech.
+    /// Set to 1.0 to disable this check.
+    ///
+    /// Defaults to 0.35.
+    pub max_zero_crossing_rate: f32,
+    /// How long speech continues after the last frame that counts as speech, in milliseconds.
+    /// This keeps short pauses between words from splitting the speech.
+    ///
+    /// Defaults to 300.
+    pub hangover_ms: u32,
+    /// Speech shorter than this is dropped, in milliseconds.
+    ///
+    /// Defaults to 250.
+    pub min_speech_ms: u32,
+    /// Silence kept before and after each stretch of speech, in milliseconds.
+    ///
+    /// Defaults to 100.
+    pub padding_ms: u32,
+}
+
+impl Default for VadConfig {
+    fn default() -> Self {
+        Self {
+            frame_ms: 30,
+            energy_threshold: 0.01,
+            max_zero_crossing_rate: 0.35,
+            hangover_ms: 300,
+            min_speech_ms: 250,
+            padding_ms: 100,
+        }
+    }
+}
+
+impl VadConfig {
+    fn frame_len(&self) -> usize {
+        ms_to_samples(self.frame_ms).max(1)
+    }
+
+    /// Does this frame of audio count as speech?
+    ///
+    /// Unlike [detect_speech], this takes neither the hangover nor the minimum speech length into account.
+    pub fn is_speech(&self, frame: &[f32]) -> bool {
+        if frame.is_empty() {
+            return false;
+        }
+        let energy = frame.iter().map(|s| s * s).sum::<f32>() / frame.len() as f32;
+        let crossings = frame
+            .windows(2)
+            .filter(|w| (w[0] >= 0.0) != (w[1] >= 0.0))
+            .count();
+        let zero_crossing_rate = crossings as f32 / frame.len() as f32;
+        energy.sqrt() >= self.energy_threshold && zero_crossing_rate <= self.max_zero_crossing_rate
+    }
+}
+
+fn ms_to_samples(ms: u32) -> usize {
+    ms as usize * SAMPLE_RATE / 1000
+}
+
+/// Find the parts of the audio that contain speech.
+///
+/// # Arguments
+/// * samples: 16 kHz mono audio, as passed to [WhisperContext::full](crate::WhisperContext::full).
+/// * config: Thresholds to use.
+///
+/// # Returns
+/// The sample ranges that contain speech, in order and without overlap.
+pub fn detect_speech(samples: &[f32], config: &VadConfig) -> Vec<Range<usize>> {
+    let frame_len = config.frame_len();
+    let hangover_frames = ms_to_samples(config.hangover_ms) / frame_len;
+    let min_speech = ms_to_samples(config.min_speech_ms);
+    let padding = ms_to_samples(config.padding_ms);
+
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    let mut current: Option<Range<usize>> = None;
+    let mut silent_frames = 0;
+    for (i, frame) in samples.chunks(frame_len).enumerate() {
+        let start = i * frame_len;
+        let end = start + frame.len();
+        if config.is_speech(frame) {
+            silent_frames = 0;
+            match &mut current {
+                Some(range) => range.end = end,
+                None => current = Some(start..end),
+            }
+        } else if let Some(range) = &current {
+            silent_frames += 1;
+            if silent_frames > hangover_frames {
+                push_range(
+                    &mut ranges,
+                    range.clone(),
+                    min_speech,
+                    padding,
+                    samples.len(),
+                );
+                current = None;
+            }
+        }
+    }
+    if let Some(range) = current {
+        push_range(&mut ranges, range, min_speech, padding, samples.len());
+    }
+    ranges
+}
+
+fn push_range(
+    ranges: &mut Vec<Range<usize>>,
+    range: Range<usize>,
+    min_speech: usize,
+    padding: usize,
+    len: usize,
+) {
+    if range.len() < min_speech {
+        return;
+    }
+    let padded = range.start.saturating_sub(padding)..(range.end + padding).min(len);
+    // padding may make neighbouring ranges overlap
+    match ranges.last_mut() {
+        Some(last) if last.end >= padded.start => last.end = padded.end,
+        _ => ranges.push(padded),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn tone(seconds: f32) -> Vec<f32> {
+        (0..(seconds * SAMPLE_RATE as f32) as usize)
+            .map(|i| (i as f32 * 440.0 * std::f32::consts::TAU / SAMPLE_RATE as f32).sin() * 0.5)
+            .collect()
+    }
+
+    #[test]
+    fn assert_detects_speech() {
+        let mut audio = vec![0.0; SAMPLE_RATE];
+        audio.extend(tone(1.0));
+        audio.extend(vec![0.0; SAMPLE_RATE]);
+
+        let config = VadConfig {
+            padding_ms: 0,
+            ..Default::default()
+        };
+        let ranges = detect_speech(&audio, &config);
+        assert_eq!(ranges.len(), 1);
+        let range = &ranges[0];
+        // frames straddling the edges of the tone may go either way
+        let frame_len = config.frame_len();
+        assert!(range.start.abs_diff(SAMPLE_RATE) <= frame_len);
+        assert!(range.end.abs_diff(2 * SAMPLE_RATE) <= frame_len);
+    }
+
+    #[test]
+    fn assert_ignores_silence_and_short_noise() {
+        let mut audio = vec![0.0; SAMPLE_RATE];
+        audio.extend(tone(0.1));
+        audio.extend(vec![0.0; SAMPLE_RATE]);
+        assert!(detect_speech(&audio, &VadConfig::default()).is_empty());
+    }
+}
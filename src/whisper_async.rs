@@ -0,0 +1,109 @@
+//! Async wrappers around [WhisperContext] that run the model on tokio's blocking thread pool.
+//!
+//! Only available with the `async` feature. These functions must be called from within a tokio runtime.
+
+use crate::{FullParams, Segment, Transcript, WhisperContext, WhisperError};
+use std::ffi::c_int;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::sync::mpsc;
+use tokio::task::{JoinError, JoinHandle};
+
+/// Handle to the blocking task started by [WhisperContext::full_stream].
+///
+/// Resolves to the context, and the return value of [WhisperContext::full].
+pub type FullStreamHandle = JoinHandle<(WhisperContext, Result<c_int, WhisperError>)>;
+
+impl WhisperContext {
+    /// Run [WhisperContext::full] on tokio's blocking thread pool, then collect the results into a [Transcript].
+    ///
+    /// The context is moved onto the blocking thread for the duration of the run,
+    /// and handed back alongside the result.
+    ///
+    /// # Arguments
+    /// * params: [crate::FullParams] struct.
+    /// * pcm: PCM audio data.
+    ///
+    /// # Returns
+    /// The context, and Ok(Transcript) on success, Err(WhisperError) on failure.
+    ///
+    /// # Panics
+    /// If running the model panicked, the panic is resumed on the calling task.
+    pub async fn full_async(
+        mut self,
+        params: FullParams,
+        data: Vec<f32>,
+    ) -> (Self, Result<Transcript, WhisperError>) {
+        let task = tokio::task::spawn_blocking(move || {
+            let ret = self
+                .full(params, &data)
+                .and_then(|_| self.collect_transcript());
+            (self, ret)
+        });
+        unwrap_join(task.await)
+    }
+
+    /// Run [WhisperContext::full] on tokio's blocking thread pool,
+    /// streaming segments back as soon as they have been decoded.
+    ///
+    /// The stream ends once the run finishes.
+    /// Await the returned handle to get the context back, along with the return value of [WhisperContext::full].
+    ///
+    /// # Arguments
+    /// * params: [crate::FullParams] struct. Its new segment callback is overwritten.
+    /// * pcm: PCM audio data.
+    ///
+    /// # Returns
+    /// A stream of segments, and a handle to the blocking task.
+    pub fn full_stream(
+        mut self,
+        params: FullParams,
+        data: Vec<f32>,
+    ) -> (SegmentStream, FullStreamHandle) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let handle = tokio::task::spawn_blocking(move || {
+            let ret = self.full_with_segment_callback(params, &data, |segment| {
+                // the receiver may have been dropped, in which case nobody is interested anymore
+                _ = tx.send(segment);
+            });
+            (self, ret)
+        });
+        (SegmentStream { rx }, handle)
+    }
+}
+
+fn unwrap_join<T>(ret: Result<T, JoinError>) -> T {
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => match e.try_into_panic() {
+            Ok(panic) => std::panic::resume_unwind(panic),
+            Err(e) => panic!("blocking whisper task failed: {}", e),
+        },
+    }
+}
+
+/// Segments streamed from [WhisperContext::full_stream].
+///
+/// This implements [futures_core::Stream], and can also be polled with [SegmentStream::next].
+#[derive(Debug)]
+pub struct SegmentStream {
+    rx: mpsc::UnboundedReceiver<Result<Segment, WhisperError>>,
+}
+
+impl SegmentStream {
+    /// Wait for the next segment.
+    ///
+    /// # Returns
+    /// The next segment, None once the run has finished and all segments have been received.
+    pub async fn next(&mut self) -> Option<Result<Segment, WhisperError>> {
+        self.rx.recv().await
+    }
+}
+
+impl futures_core::Stream for SegmentStream {
+    type Item = Result<Segment, WhisperError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.rx.poll_recv(cx)
+    }
+}
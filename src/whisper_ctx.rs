@@ -1,7 +1,22 @@
 use crate::error::WhisperError;
-use crate::whisper_params::FullParams;
+use crate::language::Language;
+use crate::memory::ModelType;
+use crate::segments::WhisperSegments;
+use crate::timestamp::Timestamp;
+use crate::transcript::{Segment, Token, Transcript};
+use crate::whisper_params::{FullParams, Task};
+use crate::{GgmlFtype, ModelHeader};
 use crate::{WhisperToken, WhisperTokenData};
 use std::ffi::{c_int, CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{self, Receiver};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// Handle to the thread started by [WhisperContext::full_with_segment_channel].
+///
+/// Joins to the context, and the return value of [WhisperContext::full].
+pub type FullThreadHandle = JoinHandle<(WhisperContext, Result<c_int, WhisperError>)>;
 
 /// Safe Rust wrapper around a Whisper context.
 ///
@@ -16,6 +31,18 @@ pub struct WhisperContext {
     encode_complete: bool,
     /// has decode been called at least once?
     decode_once: bool,
+    /// task performed by the last call to full
+    task: Task,
+    /// language passed to the last call to full
+    language: Option<Language>,
+    /// header of the model file, None if unknown
+    header: Option<ModelHeader>,
+    /// duration of the audio transcribed by the last call to full
+    processed: Duration,
+    /// scratch buffer for converting audio in full_i16, kept empty between calls to reuse its allocation
+    pcm_f32: Vec<f32>,
+    /// should the next call to full drop the text carried over from previous calls?
+    clear_context: bool,
 }
 
 impl WhisperContext {
@@ -35,17 +62,68 @@ impl WhisperContext {
         if ctx.is_null() {
             Err(WhisperError::InitError)
         } else {
-            Ok(Self {
-                ctx,
-                spectrogram_initialized: false,
-                encode_complete: false,
-                decode_once: false,
-            })
+            // SAFETY: the context was just created and is owned by nobody else
+            let mut ctx = unsafe { Self::from_raw(ctx) };
+            ctx.header = ModelHeader::read(path).ok();
+            Ok(ctx)
+        }
+    }
+
+    /// Take ownership of a context created through [whisper_rs_sys].
+    ///
+    /// This is an escape hatch for using whisper.cpp functions that are not wrapped by this crate yet.
+    /// The context is freed when the returned value is dropped.
+    ///
+    /// # Safety
+    /// `ctx` must be a valid, non-null pointer returned by one of the `whisper_init*` functions,
+    /// which is not freed or owned by anything else afterwards.
+    pub unsafe fn from_raw(ctx: *mut whisper_rs_sys::whisper_context) -> Self {
+        Self {
+            ctx,
+            spectrogram_initialized: false,
+            encode_complete: false,
+            decode_once: false,
+            task: Task::default(),
+            language: None,
+            header: None,
+            processed: Duration::ZERO,
+            pcm_f32: Vec::new(),
+            clear_context: false,
         }
     }
 
+    /// Get the raw pointer to the context, e.g. to call a function of [whisper_rs_sys] that is not wrapped yet.
+    ///
+    /// The pointer stays owned by this context, and is valid as long as it is.
+    /// Functions changing the state of the context through it (e.g. `whisper_pcm_to_mel`) are not tracked,
+    /// so the checks of the safe wrappers that rely on that state may be wrong afterwards.
+    #[inline]
+    pub fn as_ptr(&self) -> *mut whisper_rs_sys::whisper_context {
+        self.ctx
+    }
+
+    /// Give up ownership of the context, returning the raw pointer to it.
+    ///
+    /// The context is no longer freed automatically. Free it with `whisper_rs_sys::whisper_free`,
+    /// or take ownership of it again with [WhisperContext::from_raw].
+    pub fn into_raw(self) -> *mut whisper_rs_sys::whisper_context {
+        let mut this = std::mem::ManuallyDrop::new(self);
+        // SAFETY: `this` is never used or dropped again, so the buffer is dropped exactly once
+        unsafe { std::ptr::drop_in_place(&mut this.pcm_f32) };
+        this.ctx
+    }
+
     /// Create a new WhisperContext from a buffer.
     ///
+    /// whisper.cpp copies the weights out of the buffer, so it can be dropped once this returns.
+    /// This also makes it possible to ship a single binary with a small model embedded in it:
+    ///
+    /// ```ignore
+    /// static MODEL: &[u8] = include_bytes!("../models/ggml-tiny.en.bin");
+    ///
+    /// let ctx = whisper_rs::WhisperContext::new_from_buffer(MODEL)?;
+    /// ```
+    ///
     /// # Arguments
     /// * buffer: The buffer containing the model.
     ///
@@ -60,15 +138,38 @@ impl WhisperContext {
         if ctx.is_null() {
             Err(WhisperError::InitError)
         } else {
-            Ok(Self {
-                ctx,
-                spectrogram_initialized: false,
-                encode_complete: false,
-                decode_once: false,
-            })
+            // SAFETY: the context was just created and is owned by nobody else
+            let mut ctx = unsafe { Self::from_raw(ctx) };
+            ctx.header = ModelHeader::parse(buffer).ok();
+            Ok(ctx)
         }
     }
 
+    /// Create a new WhisperContext from a memory-mapped file.
+    ///
+    /// This behaves like [WhisperContext::new_from_buffer], but the file is never fully read into memory:
+    /// whisper.cpp copies the weights straight out of the mapping, which is unmapped again once loading finishes.
+    /// With large models, this roughly halves peak memory usage during startup.
+    ///
+    /// Only available with the `mmap` feature.
+    ///
+    /// # Arguments
+    /// * path: The path to the model file.
+    ///
+    /// # Returns
+    /// Ok(Self) on success, Err(WhisperError) on failure.
+    ///
+    /// # C++ equivalent
+    /// `struct whisper_context * whisper_init_from_buffer(const char * buffer, int n_bytes);`
+    #[cfg(feature = "mmap")]
+    pub fn new_mmap(path: &str) -> Result<Self, WhisperError> {
+        let file = std::fs::File::open(path).map_err(|_| WhisperError::InitError)?;
+        // SAFETY: the mapping is only read while loading, and dropped before this function returns.
+        // modifying the file while it is being loaded is UB, as it would be for any other mapped file.
+        let mmap = unsafe { memmap2::Mmap::map(&file) }.map_err(|_| WhisperError::InitError)?;
+        Self::new_from_buffer(&mmap)
+    }
+
     // we don't implement `whisper_init()` here since i have zero clue what `whisper_model_loader` does
 
     /// Convert raw PCM audio (floating point 32 bit) to log mel spectrogram.
@@ -114,7 +215,8 @@ impl WhisperContext {
     /// See instead [WhisperContext::pcm_to_mel].
     ///
     /// # Arguments
-    /// * data: The log mel spectrogram.
+    /// * data: The log mel spectrogram, as consecutive frames of 80 mel bands each.
+    ///   The length must be a multiple of 80, returns an error otherwise.
     ///
     /// # Returns
     /// Ok(()) on success, Err(WhisperError) on failure.
@@ -122,12 +224,16 @@ impl WhisperContext {
     /// # C++ equivalent
     /// `int whisper_set_mel(struct whisper_context * ctx, const float * data, int n_len, int n_mel)`
     pub fn set_mel(&mut self, data: &[f32]) -> Result<(), WhisperError> {
+        let n_mel = whisper_rs_sys::WHISPER_N_MEL as usize;
+        if !data.len().is_multiple_of(n_mel) {
+            return Err(WhisperError::InvalidMelBands);
+        }
         let ret = unsafe {
             whisper_rs_sys::whisper_set_mel(
                 self.ctx,
                 data.as_ptr(),
-                data.len() as c_int,
-                80 as c_int,
+                (data.len() / n_mel) as c_int,
+                n_mel as c_int,
             )
         };
         if ret == -1 {
@@ -153,6 +259,7 @@ impl WhisperContext {
     /// # C++ equivalent
     /// `int whisper_encode(struct whisper_context * ctx, int offset, int n_threads)`
     pub fn encode(&mut self, offset: usize, threads: usize) -> Result<(), WhisperError> {
+        self.check_mel_bands()?;
         if !self.spectrogram_initialized {
             return Err(WhisperError::SpectrogramNotInitialized);
         }
@@ -221,6 +328,8 @@ impl WhisperContext {
     ///
     /// # Arguments
     /// * text: The text to convert.
+    /// * max_tokens: The maximum number of tokens to return. If the text converts into more, this fails.
+    ///   See [WhisperContext::tokenize_all] to avoid picking a limit.
     ///
     /// # Returns
     /// Ok(Vec<WhisperToken>) on success, Err(WhisperError) on failure.
@@ -232,12 +341,13 @@ impl WhisperContext {
         text: &str,
         max_tokens: usize,
     ) -> Result<Vec<WhisperToken>, WhisperError> {
+        let text = CString::new(text)?;
         // allocate at least max_tokens to ensure the memory is valid
         let mut tokens: Vec<WhisperToken> = Vec::with_capacity(max_tokens);
         let ret = unsafe {
             whisper_rs_sys::whisper_tokenize(
                 self.ctx,
-                text.as_ptr() as *const _,
+                text.as_ptr(),
                 tokens.as_mut_ptr(),
                 max_tokens as c_int,
             )
@@ -251,6 +361,30 @@ impl WhisperContext {
         }
     }
 
+    /// Convert the provided text into tokens, without having to guess how many there will be.
+    ///
+    /// The vocabulary is byte-level, so text never turns into more tokens than it has bytes.
+    ///
+    /// # Arguments
+    /// * text: The text to convert.
+    ///
+    /// # Returns
+    /// Ok(Vec<WhisperToken>) on success, Err(WhisperError) on failure.
+    pub fn tokenize_all(&mut self, text: &str) -> Result<Vec<WhisperToken>, WhisperError> {
+        self.tokenize(text, text.len().max(1))
+    }
+
+    /// Count the tokens the provided text converts into, e.g. to check it fits in the prompt.
+    ///
+    /// # Arguments
+    /// * text: The text to count the tokens of.
+    ///
+    /// # Returns
+    /// Ok(usize) on success, Err(WhisperError) on failure.
+    pub fn token_count(&mut self, text: &str) -> Result<usize, WhisperError> {
+        self.tokenize_all(text).map(|tokens| tokens.len())
+    }
+
     // Language functions
     /// Use mel data at offset_ms to try and auto-detect the spoken language
     /// Make sure to call pcm_to_mel() or set_mel() first
@@ -260,7 +394,9 @@ impl WhisperContext {
     /// * n_threads: How many threads to use. Defaults to 1. Must be at least 1, returns an error otherwise.
     ///
     /// # Returns
-    /// Ok(Vec<f32>) on success, Err(WhisperError) on failure.
+    /// Ok(Vec<(Language, f32)>) on success, Err(WhisperError) on failure.
+    /// The vector holds the probability of every language, sorted from most to least likely,
+    /// so the first entry is the detected language.
     ///
     /// # C++ equivalent
     /// `int whisper_lang_auto_detect(struct whisper_context * ctx, int offset_ms, int n_threads, float * lang_probs)`
@@ -268,13 +404,16 @@ impl WhisperContext {
         &mut self,
         offset_ms: usize,
         threads: usize,
-    ) -> Result<Vec<f32>, WhisperError> {
+    ) -> Result<Vec<(Language, f32)>, WhisperError> {
+        self.check_mel_bands()?;
         if !self.spectrogram_initialized {
             return Err(WhisperError::SpectrogramNotInitialized);
         }
         if threads < 1 {
             return Err(WhisperError::InvalidThreadCount);
         }
+        // language detection runs the decoder, overwriting the logits of a previous decode call
+        self.decode_once = false;
         let mut lang_probs: Vec<f32> = vec![0.0; crate::standalone::get_lang_max_id() as usize + 1];
         let ret = unsafe {
             whisper_rs_sys::whisper_lang_auto_detect(
@@ -284,25 +423,49 @@ impl WhisperContext {
                 lang_probs.as_mut_ptr(),
             )
         };
-        if ret == -1 {
-            Err(WhisperError::UnableToCalculateEvaluation)
+        // on success, the ID of the most likely language is returned
+        if ret == -6 {
+            Err(WhisperError::FailedToEncode)
+        } else if ret == -7 {
+            Err(WhisperError::FailedToDecode)
+        } else if ret < 0 {
+            Err(WhisperError::GenericError(ret))
         } else {
-            assert_eq!(
-                ret as usize,
-                lang_probs.len(),
-                "lang_probs length mismatch: this is a bug in whisper.cpp"
-            );
-            // if we're still running, double check that the length is correct, otherwise print to stderr
-            // and abort, as this will cause Undefined Behavior
-            // might get here due to the unwind being caught by a user-installed panic handler
-            if lang_probs.len() != ret as usize {
-                eprintln!("lang_probs length mismatch: this is a bug in whisper.cpp, aborting");
-                std::process::abort();
-            }
-            Ok(lang_probs)
+            let mut langs = lang_probs
+                .into_iter()
+                .enumerate()
+                .filter_map(|(id, p)| Language::from_id(id as c_int).map(|lang| (lang, p)))
+                .collect::<Vec<_>>();
+            langs.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+            Ok(langs)
         }
     }
 
+    /// Detect the spoken language of some audio, without transcribing it.
+    ///
+    /// This computes the spectrogram of the first 30 seconds of the audio, and runs [WhisperContext::lang_detect] on it.
+    /// It's much cheaper than a full run, e.g. for routing audio to language-specific pipelines.
+    ///
+    /// # Arguments
+    /// * pcm: PCM audio data.
+    /// * threads: How many threads to use. Must be at least 1, returns an error otherwise.
+    ///
+    /// # Returns
+    /// Ok((Language, Vec<(Language, f32)>)) with the most likely language,
+    /// and the probability of every language sorted from most to least likely, on success.
+    /// Err(WhisperError) on failure.
+    pub fn detect_language(
+        &mut self,
+        pcm: &[f32],
+        threads: usize,
+    ) -> Result<(Language, Vec<(Language, f32)>), WhisperError> {
+        let window = 30 * whisper_rs_sys::WHISPER_SAMPLE_RATE as usize;
+        self.pcm_to_mel(&pcm[..pcm.len().min(window)], threads)?;
+        let langs = self.lang_detect(0, threads)?;
+        let &(language, _) = langs.first().ok_or(WhisperError::FailedToDetectLanguage)?;
+        Ok((language, langs))
+    }
+
     // model attributes
     /// Get the mel spectrogram length.
     ///
@@ -363,41 +526,48 @@ impl WhisperContext {
 
     // logit functions
     /// Get the logits obtained from the last call to [WhisperContext::decode].
-    /// The logits for the last token are stored in the last row of the matrix.
     ///
-    /// Note: this function may be somewhat expensive depending on the size of the matrix returned, as it
-    /// needs to be rebuilt from the raw data. Try to avoid calling it more than once if possible.
+    /// The bundled whisper.cpp only keeps the logits of the last token it decoded,
+    /// so the matrix has a single row of n_vocab logits, whichever segment is passed.
+    /// Prefer [WhisperContext::logits], which doesn't copy them.
     ///
     /// # Arguments
-    /// * segment: The segment to fetch data for.
+    /// * segment: Ignored, kept for compatibility.
     ///
     /// # Returns
-    /// 2D matrix of logits. Row count is equal to n_tokens, column count is equal to n_vocab.
+    /// 2D matrix of logits. Row count is 1, column count is equal to n_vocab.
     ///
     /// # C++ equivalent
     /// `float * whisper_get_logits(struct whisper_context * ctx)`
-    pub fn get_logits(&self, segment: c_int) -> Result<Vec<Vec<f32>>, WhisperError> {
+    pub fn get_logits(&self, _segment: c_int) -> Result<Vec<Vec<f32>>, WhisperError> {
         if !self.spectrogram_initialized {
             return Err(WhisperError::SpectrogramNotInitialized);
         }
+        Ok(vec![self.logits()?.to_vec()])
+    }
 
-        let ret = unsafe { whisper_rs_sys::whisper_get_logits(self.ctx) };
-        if ret.is_null() {
-            return Err(WhisperError::NullPointer);
+    /// Get the logits for the last token passed to the last call to [WhisperContext::decode], without copying them.
+    ///
+    /// The bundled whisper.cpp only keeps the logits of the last token,
+    /// not those of every token passed to decode.
+    ///
+    /// # Returns
+    /// Ok(&[f32]) of length n_vocab on success, Err(WhisperError) on failure.
+    ///
+    /// # C++ equivalent
+    /// `float * whisper_get_logits(struct whisper_context * ctx)`
+    pub fn logits(&self) -> Result<&[f32], WhisperError> {
+        if !self.decode_once {
+            return Err(WhisperError::DecodeNotComplete);
         }
-        let mut logits = Vec::new();
-        let n_vocab = self.n_vocab();
-        let n_tokens = self.full_n_tokens(segment);
-        for i in 0..n_tokens {
-            let mut row = Vec::new();
-            for j in 0..n_vocab {
-                let idx = (i * n_vocab) + j;
-                let val = unsafe { *ret.offset(idx as isize) };
-                row.push(val);
-            }
-            logits.push(row);
+        // SAFETY: whisper_decode leaves the n_vocab logits of the last token in the context,
+        // and they can only be overwritten through &mut self
+        unsafe {
+            logits_row(
+                whisper_rs_sys::whisper_get_logits(self.ctx),
+                self.n_vocab() as usize,
+            )
         }
-        Ok(logits)
     }
 
     // token functions
@@ -421,6 +591,46 @@ impl WhisperContext {
         Ok(r_str.to_string())
     }
 
+    /// Get the raw bytes of a token.
+    ///
+    /// Whisper uses a byte-level vocabulary, so a single token may hold only part of a multi-byte UTF-8 character,
+    /// in which case [WhisperContext::token_to_str] fails. Use this, or [WhisperContext::detokenize], instead.
+    ///
+    /// # Arguments
+    /// * token_id: ID of the token.
+    ///
+    /// # Returns
+    /// Ok(&[u8]) on success, Err(WhisperError) on failure.
+    ///
+    /// # C++ equivalent
+    /// `const char * whisper_token_to_str(struct whisper_context * ctx, whisper_token token)`
+    pub fn token_to_bytes(&self, token_id: WhisperToken) -> Result<&[u8], WhisperError> {
+        let ret = unsafe { whisper_rs_sys::whisper_token_to_str(self.ctx, token_id) };
+        if ret.is_null() {
+            return Err(WhisperError::NullPointer);
+        }
+        // SAFETY: the vocabulary lives as long as the context
+        Ok(unsafe { CStr::from_ptr(ret) }.to_bytes())
+    }
+
+    /// Convert a sequence of tokens back into text.
+    ///
+    /// The bytes of all tokens are joined before being decoded,
+    /// so characters split across several tokens are reassembled correctly.
+    ///
+    /// # Arguments
+    /// * tokens: The tokens to convert.
+    ///
+    /// # Returns
+    /// Ok(String) on success, Err(WhisperError) on failure.
+    pub fn detokenize(&self, tokens: &[WhisperToken]) -> Result<String, WhisperError> {
+        let mut bytes = Vec::new();
+        for &token in tokens {
+            bytes.extend_from_slice(self.token_to_bytes(token)?);
+        }
+        String::from_utf8(bytes).map_err(|e| e.utf8_error().into())
+    }
+
     /// Get the ID of the eot token.
     ///
     /// # C++ equivalent
@@ -487,6 +697,30 @@ impl WhisperContext {
         unsafe { whisper_rs_sys::whisper_token_lang(self.ctx, lang_id) }
     }
 
+    /// Get the ID of the translate task token.
+    ///
+    /// The bundled whisper.cpp uses the same task tokens for every model,
+    /// so this is the same as [crate::token_translate]. It is provided here for convenience.
+    ///
+    /// # C++ equivalent
+    /// `whisper_token whisper_token_translate ()`
+    #[inline]
+    pub fn token_translate(&self) -> WhisperToken {
+        crate::token_translate()
+    }
+
+    /// Get the ID of the transcribe task token.
+    ///
+    /// The bundled whisper.cpp uses the same task tokens for every model,
+    /// so this is the same as [crate::token_transcribe]. It is provided here for convenience.
+    ///
+    /// # C++ equivalent
+    /// `whisper_token whisper_token_transcribe()`
+    #[inline]
+    pub fn token_transcribe(&self) -> WhisperToken {
+        crate::token_transcribe()
+    }
+
     /// Print performance statistics to stderr.
     ///
     /// # C++ equivalent
@@ -505,6 +739,22 @@ impl WhisperContext {
         unsafe { whisper_rs_sys::whisper_reset_timings(self.ctx) }
     }
 
+    /// Reset the context, so it can be reused for an unrelated audio stream.
+    ///
+    /// whisper.cpp carries the text of previous calls to [WhisperContext::full] over as the prompt of the next one,
+    /// unless [FullParams::set_no_context] is set. After a reset, the next call starts without it,
+    /// as if no_context was set for that call. Prompt tokens set with [FullParams::set_tokens] are still used.
+    ///
+    /// This also forgets the spectrogram, encoder output and logits as far as the checks of
+    /// [WhisperContext::encode], [WhisperContext::decode] and [WhisperContext::get_logits] are concerned.
+    /// The buffers of the context are kept, so this is much cheaper than loading the model again.
+    pub fn reset(&mut self) {
+        self.spectrogram_initialized = false;
+        self.encode_complete = false;
+        self.decode_once = false;
+        self.clear_context = true;
+    }
+
     /// Run the entire model: PCM -> log mel spectrogram -> encoder -> decoder -> text
     /// Uses the specified decoding strategy to obtain the text.
     ///
@@ -516,24 +766,47 @@ impl WhisperContext {
     ///
     /// # Returns
     /// Ok(c_int) on success, Err(WhisperError) on failure.
+    /// Returns Err(WhisperError::AudioTooShort) instead of an empty result if whisper.cpp would skip the audio.
     ///
     /// # C++ equivalent
     /// `int whisper_full(struct whisper_context * ctx, struct whisper_full_params params, const float * samples, int n_samples)`
-    pub fn full(&mut self, params: FullParams, data: &[f32]) -> Result<c_int, WhisperError> {
+    pub fn full(&mut self, mut params: FullParams, data: &[f32]) -> Result<c_int, WhisperError> {
+        self.processed = self.validate_params(&params, data.len())?;
+        if let Some(prompt) = params.initial_prompt.take() {
+            let tokens = self.tokenize_all(&prompt)?;
+            params.set_tokens(&tokens);
+        }
+        if std::mem::take(&mut self.clear_context) {
+            params.set_no_context(true);
+        }
+        // the logits of a previous decode call are overwritten by running the model
+        self.decode_once = false;
+        self.task = params.task();
+        self.language = params.language();
         let ret = unsafe {
             whisper_rs_sys::whisper_full(self.ctx, params.fp, data.as_ptr(), data.len() as c_int)
         };
-        if ret == -1 {
-            Err(WhisperError::UnableToCalculateSpectrogram)
-        } else if ret == 7 {
-            Err(WhisperError::FailedToEncode)
-        } else if ret == 8 {
-            Err(WhisperError::FailedToDecode)
-        } else if ret == 0 {
-            Ok(ret)
-        } else {
-            Err(WhisperError::GenericError(ret))
-        }
+        full_result(ret)
+    }
+
+    /// Run the entire model like [WhisperContext::full], on 16 bit PCM audio.
+    ///
+    /// The audio is converted in a buffer owned by the context, which is reused by the next call,
+    /// so transcribing audio from a capture device in a loop doesn't allocate once the buffer is large enough.
+    ///
+    /// # Arguments
+    /// * params: [crate::FullParams] struct.
+    /// * data: 16 bit mono PCM audio data.
+    ///
+    /// # Returns
+    /// Ok(c_int) on success, Err(WhisperError) on failure.
+    pub fn full_i16(&mut self, params: FullParams, data: &[i16]) -> Result<c_int, WhisperError> {
+        let mut pcm = std::mem::take(&mut self.pcm_f32);
+        pcm.extend(data.iter().map(|&sample| sample as f32 / 32768.0));
+        let ret = self.full(params, &pcm);
+        pcm.clear();
+        self.pcm_f32 = pcm;
+        ret
     }
 
     /// Split the input audio into chunks and delegate to [WhisperContext::full].
@@ -553,10 +826,22 @@ impl WhisperContext {
     /// `int whisper_full_parallel(struct whisper_context * ctx, struct whisper_full_params params, const float * samples, int n_samples, int n_processors)`
     pub fn full_parallel(
         &mut self,
-        params: FullParams,
+        mut params: FullParams,
         data: &[f32],
         n_processors: c_int,
     ) -> Result<c_int, WhisperError> {
+        self.processed = self.validate_params(&params, data.len())?;
+        if let Some(prompt) = params.initial_prompt.take() {
+            let tokens = self.tokenize_all(&prompt)?;
+            params.set_tokens(&tokens);
+        }
+        if std::mem::take(&mut self.clear_context) {
+            params.set_no_context(true);
+        }
+        // the logits of a previous decode call are overwritten by running the model
+        self.decode_once = false;
+        self.task = params.task();
+        self.language = params.language();
         let ret = unsafe {
             whisper_rs_sys::whisper_full_parallel(
                 self.ctx,
@@ -566,19 +851,9 @@ impl WhisperContext {
                 n_processors,
             )
         };
-        if ret == -1 {
-            Err(WhisperError::UnableToCalculateSpectrogram)
-        } else if ret == 7 {
-            Err(WhisperError::FailedToEncode)
-        } else if ret == 8 {
-            Err(WhisperError::FailedToDecode)
-        } else if ret == 0 {
-            // note 0 is returned on success and also when initializing other contexts fails,
-            // causing some audio to not be processed
-            Ok(ret)
-        } else {
-            Err(WhisperError::GenericError(ret))
-        }
+        // note 0 is returned on success and also when initializing other contexts fails,
+        // causing some audio to not be processed
+        full_result(ret)
     }
 
     /// Number of generated text segments.
@@ -591,6 +866,22 @@ impl WhisperContext {
         unsafe { whisper_rs_sys::whisper_full_n_segments(self.ctx) }
     }
 
+    /// Iterate over the segments of the last call to [WhisperContext::full].
+    ///
+    /// ```no_run
+    /// # fn main() -> Result<(), whisper_rs::WhisperError> {
+    /// # let ctx = whisper_rs::WhisperContext::new("model.bin")?;
+    /// for segment in ctx.segments() {
+    ///     println!("[{} - {}]: {}", segment.start(), segment.end(), segment.text()?);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub fn segments(&self) -> WhisperSegments<'_> {
+        WhisperSegments::new(self)
+    }
+
     /// Get the start time of the specified segment.
     ///
     /// # Arguments
@@ -615,6 +906,24 @@ impl WhisperContext {
         unsafe { whisper_rs_sys::whisper_full_get_segment_t1(self.ctx, segment) }
     }
 
+    /// Get the start time of the specified segment as a [Duration].
+    ///
+    /// # Arguments
+    /// * segment: Segment index.
+    #[inline]
+    pub fn segment_start_time(&self, segment: c_int) -> Duration {
+        Timestamp::from_centiseconds(self.full_get_segment_t0(segment)).as_duration()
+    }
+
+    /// Get the end time of the specified segment as a [Duration].
+    ///
+    /// # Arguments
+    /// * segment: Segment index.
+    #[inline]
+    pub fn segment_end_time(&self, segment: c_int) -> Duration {
+        Timestamp::from_centiseconds(self.full_get_segment_t1(segment)).as_duration()
+    }
+
     /// Get the text of the specified segment.
     ///
     /// # Arguments
@@ -626,13 +935,30 @@ impl WhisperContext {
     /// # C++ equivalent
     /// `const char * whisper_full_get_segment_text(struct whisper_context * ctx, int i_segment)`
     pub fn full_get_segment_text(&self, segment: c_int) -> Result<String, WhisperError> {
-        let ret = unsafe { whisper_rs_sys::whisper_full_get_segment_text(self.ctx, segment) };
-        if ret.is_null() {
-            return Err(WhisperError::NullPointer);
-        }
-        let c_str = unsafe { CStr::from_ptr(ret) };
-        let r_str = c_str.to_str()?;
-        Ok(r_str.to_string())
+        self.full_get_segment_text_str(segment).map(str::to_string)
+    }
+
+    /// Get the text of the specified segment without copying it.
+    ///
+    /// The text is borrowed from the results stored in the context, which the next call to
+    /// [WhisperContext::full] overwrites.
+    ///
+    /// # Arguments
+    /// * segment: Segment index.
+    ///
+    /// # Returns
+    /// Ok(&str) on success, Err(WhisperError) on failure.
+    ///
+    /// # C++ equivalent
+    /// `const char * whisper_full_get_segment_text(struct whisper_context * ctx, int i_segment)`
+    pub fn full_get_segment_text_str(&self, segment: c_int) -> Result<&str, WhisperError> {
+        // SAFETY: the results are only modified through &mut self
+        let text = unsafe {
+            c_str(whisper_rs_sys::whisper_full_get_segment_text(
+                self.ctx, segment,
+            ))
+        }?;
+        Ok(text.to_str()?)
     }
 
     /// Get number of tokens in the specified segment.
@@ -721,6 +1047,471 @@ impl WhisperContext {
     pub fn full_get_token_prob(&self, segment: c_int, token: c_int) -> f32 {
         unsafe { whisper_rs_sys::whisper_full_get_token_p(self.ctx, segment, token) }
     }
+
+    /// Number of mel bands the model expects its input to have, read from the model's header.
+    ///
+    /// Models other than large-v3 expect [WHISPER_N_MEL](whisper_rs_sys::WHISPER_N_MEL),
+    /// the only number the bundled whisper.cpp can compute. Running the encoder on any other model
+    /// returns [WhisperError::UnsupportedMelBands] instead of aborting.
+    ///
+    /// # Returns
+    /// The number of mel bands, None if it is unknown because the context was created with [WhisperContext::from_raw].
+    #[inline]
+    pub fn model_n_mels(&self) -> Option<c_int> {
+        self.header.map(|header| header.n_mels)
+    }
+
+    /// The size class of the model, read from the model's header.
+    ///
+    /// # Returns
+    /// The model type, None if it is unknown because the context was created with [WhisperContext::from_raw].
+    #[inline]
+    pub fn model_type(&self) -> Option<ModelType> {
+        self.header.map(|header| header.model_type())
+    }
+
+    /// How the weights of the model are stored, read from the model's header.
+    ///
+    /// # Returns
+    /// The type of the weights, None if it is unknown because the context was created with [WhisperContext::from_raw].
+    #[inline]
+    pub fn model_ftype(&self) -> Option<GgmlFtype> {
+        self.header.map(|header| header.ftype)
+    }
+
+    /// The header of the model file, with all of its hyperparameters.
+    ///
+    /// # Returns
+    /// The header, None if it is unknown because the context was created with [WhisperContext::from_raw].
+    #[inline]
+    pub fn model_header(&self) -> Option<ModelHeader> {
+        self.header
+    }
+
+    fn check_mel_bands(&self) -> Result<(), WhisperError> {
+        self.header
+            .map_or(Ok(()), |header| header.check_compatible())
+    }
+
+    /// Duration of the audio transcribed by the last call to [WhisperContext::full],
+    /// after applying the offset and duration of its parameters.
+    #[inline]
+    pub fn full_processed_duration(&self) -> Duration {
+        self.processed
+    }
+
+    /// Check the parameters that can only be validated against the loaded model and the audio.
+    ///
+    /// # Returns
+    /// Ok(Duration) with the duration of the audio that will be transcribed, Err(WhisperError) on failure.
+    fn validate_params(
+        &self,
+        params: &FullParams,
+        n_samples: usize,
+    ) -> Result<Duration, WhisperError> {
+        self.check_mel_bands()?;
+        params.sampling_strategy.validate()?;
+        if !(0..=self.n_audio_ctx()).contains(&params.fp.audio_ctx) {
+            return Err(WhisperError::InvalidAudioCtx);
+        }
+        if n_samples > c_int::MAX as usize {
+            return Err(WhisperError::AudioTooLong);
+        }
+        if params.is_too_short(n_samples) {
+            return Err(WhisperError::AudioTooShort);
+        }
+        Ok(params.processed_duration(n_samples))
+    }
+
+    /// Collect the results of the last call to [WhisperContext::full] into an owned [Transcript].
+    ///
+    /// The returned value does not borrow from the context,
+    /// so it can be kept around or sent to another thread after the context is reused or dropped.
+    ///
+    /// # Returns
+    /// Ok(Transcript) on success, Err(WhisperError) on failure.
+    pub fn collect_transcript(&self) -> Result<Transcript, WhisperError> {
+        let mut transcript = Transcript::default();
+        self.collect_transcript_into(&mut transcript)?;
+        Ok(transcript)
+    }
+
+    /// Collect the results of the last call to [WhisperContext::full] into an existing [Transcript],
+    /// like [WhisperContext::collect_transcript].
+    ///
+    /// The strings and vectors already in the transcript are overwritten in place, so reusing the
+    /// same transcript across calls avoids most allocations once it has grown to the usual size of a result.
+    ///
+    /// # Arguments
+    /// * transcript: The transcript to overwrite.
+    ///
+    /// # Returns
+    /// Ok(()) on success, Err(WhisperError) on failure. The contents of the transcript are unspecified on failure.
+    pub fn collect_transcript_into(&self, transcript: &mut Transcript) -> Result<(), WhisperError> {
+        let n_segments = self.full_n_segments().max(0) as usize;
+        transcript.segments.truncate(n_segments);
+        for segment in 0..n_segments {
+            if segment == transcript.segments.len() {
+                transcript.segments.push(Segment {
+                    text: String::new(),
+                    t0: 0,
+                    t1: 0,
+                    tokens: Vec::new(),
+                });
+            }
+            unsafe {
+                read_segment_into(
+                    self.ctx,
+                    segment as c_int,
+                    &mut transcript.segments[segment],
+                )
+            }?;
+        }
+        transcript.task = self.task;
+        transcript.language = self.language;
+        transcript.duration = self.processed;
+        Ok(())
+    }
+
+    /// Get the specified segment of the last call to [WhisperContext::full] as an owned [Segment].
+    ///
+    /// # Arguments
+    /// * segment: Segment index.
+    ///
+    /// # Returns
+    /// Ok(Segment) on success, Err(WhisperError) on failure.
+    pub fn full_get_segment(&self, segment: c_int) -> Result<Segment, WhisperError> {
+        unsafe { get_segment(self.ctx, segment) }
+    }
+
+    /// Get the text tokens of the last call to [WhisperContext::full], to use as the prompt of the next one.
+    ///
+    /// With [FullParams::set_no_context] left at false, this context already carries the prompt over
+    /// on its own. This is for passing it on explicitly with [FullParams::set_tokens],
+    /// e.g. when the next chunk of a stream is transcribed by a different context.
+    ///
+    /// # Arguments
+    /// * max_tokens: The maximum number of tokens to return. The most recent ones are kept.
+    ///   whisper.cpp itself keeps at most half of [WhisperContext::n_text_ctx].
+    ///
+    /// # Returns
+    /// The tokens, oldest first. Special tokens such as timestamps are left out.
+    pub fn carry_over_prompt(&self, max_tokens: usize) -> Vec<WhisperToken> {
+        let eot = self.token_eot();
+        let mut tokens: Vec<WhisperToken> = self
+            .segments()
+            .flat_map(|segment| segment.tokens())
+            .map(|token| token.id)
+            .filter(|&id| id < eot)
+            .collect();
+        let excess = tokens.len().saturating_sub(max_tokens);
+        tokens.drain(..excess);
+        tokens
+    }
+
+    /// Run the entire model like [WhisperContext::full],
+    /// calling `callback` with every new segment as soon as it has been decoded.
+    ///
+    /// This is a safe alternative to [FullParams::set_new_segment_callback],
+    /// which is overwritten by this function. If `callback` panics, the panic is caught
+    /// before it reaches whisper.cpp, and the run is stopped as described in
+    /// [WhisperContext::try_full_with_segment_callback].
+    ///
+    /// # Arguments
+    /// * params: [crate::FullParams] struct.
+    /// * pcm: PCM audio data.
+    /// * callback: Called on the current thread with each new segment,
+    ///   or an error if the segment couldn't be read.
+    ///
+    /// # Returns
+    /// Ok(c_int) on success, Err(WhisperError) on failure.
+    /// Err(WhisperError::CallbackPanicked) if `callback` panicked.
+    pub fn full_with_segment_callback<F>(
+        &mut self,
+        params: FullParams,
+        data: &[f32],
+        mut callback: F,
+    ) -> Result<c_int, WhisperError>
+    where
+        F: FnMut(Result<Segment, WhisperError>),
+    {
+        self.try_full_with_segment_callback(params, data, |segment| {
+            callback(segment);
+            Ok(())
+        })
+    }
+
+    /// Run the entire model like [WhisperContext::full_with_segment_callback],
+    /// with a callback that can stop the run by returning an error.
+    ///
+    /// whisper.cpp can't be interrupted in the middle of a 30 second window. Once `callback` fails or panics,
+    /// it isn't called again, and the run stops before the next window is encoded.
+    /// This overwrites both the new segment callback and the start encoder callback of the parameters.
+    ///
+    /// # Arguments
+    /// * params: [crate::FullParams] struct.
+    /// * pcm: PCM audio data.
+    /// * callback: Called on the current thread with each new segment,
+    ///   or an error if the segment couldn't be read. Return an error to stop the run.
+    ///
+    /// # Returns
+    /// Ok(c_int) on success.
+    /// Err with the error returned by `callback`, or [WhisperError::CallbackPanicked] if it panicked.
+    /// Err(WhisperError) if running the model failed otherwise.
+    pub fn try_full_with_segment_callback<F, E>(
+        &mut self,
+        mut params: FullParams,
+        data: &[f32],
+        callback: F,
+    ) -> Result<c_int, E>
+    where
+        F: FnMut(Result<Segment, WhisperError>) -> Result<(), E>,
+        E: From<WhisperError>,
+    {
+        struct State<F, E> {
+            callback: F,
+            error: Option<E>,
+        }
+
+        unsafe extern "C" fn on_new_segment<F, E>(
+            ctx: *mut whisper_rs_sys::whisper_context,
+            n_new: c_int,
+            user_data: *mut std::ffi::c_void,
+        ) where
+            F: FnMut(Result<Segment, WhisperError>) -> Result<(), E>,
+            E: From<WhisperError>,
+        {
+            // SAFETY: user_data is the &mut State below, which outlives the call to full
+            let state = &mut *(user_data as *mut State<F, E>);
+            let n_segments = whisper_rs_sys::whisper_full_n_segments(ctx);
+            for segment in (n_segments - n_new).max(0)..n_segments {
+                if state.error.is_some() {
+                    return;
+                }
+                let segment = get_segment(ctx, segment);
+                // unwinding into whisper.cpp is undefined behavior, so the panic ends here
+                match panic::catch_unwind(AssertUnwindSafe(|| (state.callback)(segment))) {
+                    Ok(Ok(())) => {}
+                    Ok(Err(error)) => state.error = Some(error),
+                    Err(_) => state.error = Some(WhisperError::CallbackPanicked.into()),
+                }
+            }
+        }
+
+        unsafe extern "C" fn on_encoder_begin<F, E>(
+            _ctx: *mut whisper_rs_sys::whisper_context,
+            user_data: *mut std::ffi::c_void,
+        ) -> bool {
+            // SAFETY: as above, and whisper.cpp never runs both callbacks at the same time
+            let state = &*(user_data as *const State<F, E>);
+            state.error.is_none()
+        }
+
+        let mut state = State {
+            callback,
+            error: None,
+        };
+        let user_data = &mut state as *mut State<F, E> as *mut std::ffi::c_void;
+        unsafe {
+            params.set_new_segment_callback(Some(on_new_segment::<F, E>));
+            params.set_new_segment_callback_user_data(user_data);
+            params.set_start_encoder_callback(Some(on_encoder_begin::<F, E>));
+            params.set_start_encoder_callback_user_data(user_data);
+        }
+        let ret = self.full(params, data);
+        match state.error {
+            Some(error) => Err(error),
+            None => ret.map_err(E::from),
+        }
+    }
+
+    /// Run the entire model like [WhisperContext::full] on a new thread,
+    /// sending every new segment over a channel as soon as it has been decoded.
+    ///
+    /// The channel is closed once the run finishes.
+    /// Join the returned handle to get the context back, along with the return value of [WhisperContext::full].
+    ///
+    /// ```no_run
+    /// # use whisper_rs::{FullParams, SamplingStrategy, WhisperContext};
+    /// # let ctx = WhisperContext::new("model.bin").unwrap();
+    /// # let audio = vec![0.0; 16000];
+    /// let params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+    /// let (segments, handle) = ctx.full_with_segment_channel(params, audio);
+    /// for segment in segments {
+    ///     println!("{}", segment.unwrap().text);
+    /// }
+    /// let (ctx, ret) = handle.join().unwrap();
+    /// ```
+    ///
+    /// # Arguments
+    /// * params: [crate::FullParams] struct. Its new segment callback is overwritten.
+    /// * pcm: PCM audio data.
+    ///
+    /// # Returns
+    /// A receiver for the segments, and a handle to the thread.
+    pub fn full_with_segment_channel(
+        mut self,
+        params: FullParams,
+        data: Vec<f32>,
+    ) -> (Receiver<Result<Segment, WhisperError>>, FullThreadHandle) {
+        let (tx, rx) = mpsc::channel();
+        let handle = std::thread::spawn(move || {
+            let ret = self.full_with_segment_callback(params, &data, |segment| {
+                // the receiver may have been dropped, in which case nobody is interested anymore
+                _ = tx.send(segment);
+            });
+            (self, ret)
+        });
+        (rx, handle)
+    }
+}
+
+/// Map the return value of `whisper_full` and `whisper_full_parallel` to a result.
+fn full_result(ret: c_int) -> Result<c_int, WhisperError> {
+    match ret {
+        0 => Ok(ret),
+        -1 | -2 => Err(WhisperError::UnableToCalculateSpectrogram),
+        -3 => Err(WhisperError::FailedToDetectLanguage),
+        -5 => Err(WhisperError::InvalidAudioCtx),
+        -6 => Err(WhisperError::FailedToEncode),
+        -7 | -8 => Err(WhisperError::FailedToDecode),
+        _ => Err(WhisperError::GenericError(ret)),
+    }
+}
+
+/// Read a segment of the last run out of a raw context.
+///
+/// # Safety
+/// `ctx` must be a valid pointer to a context that isn't being modified concurrently.
+unsafe fn get_segment(
+    ctx: *mut whisper_rs_sys::whisper_context,
+    segment: c_int,
+) -> Result<Segment, WhisperError> {
+    let mut ret = Segment {
+        text: String::new(),
+        t0: 0,
+        t1: 0,
+        tokens: Vec::new(),
+    };
+    read_segment_into(ctx, segment, &mut ret)?;
+    Ok(ret)
+}
+
+/// Read a segment of the last run out of a raw context, reusing the allocations of `out`.
+///
+/// # Safety
+/// `ctx` must be a valid pointer to a context that isn't being modified concurrently.
+unsafe fn read_segment_into(
+    ctx: *mut whisper_rs_sys::whisper_context,
+    segment: c_int,
+    out: &mut Segment,
+) -> Result<(), WhisperError> {
+    let n_tokens = whisper_rs_sys::whisper_full_n_tokens(ctx, segment).max(0);
+    let eot = whisper_rs_sys::whisper_token_eot(ctx);
+    out.tokens.truncate(n_tokens as usize);
+    // bytes of a multi-byte character that isn't complete yet, see push_utf8
+    let mut pending = Vec::new();
+    let mut last_text_token = None;
+    for token in 0..n_tokens {
+        let data = whisper_rs_sys::whisper_full_get_token_data(ctx, segment, token);
+        let bytes = c_str(whisper_rs_sys::whisper_full_get_token_text(
+            ctx, segment, token,
+        ))?
+        .to_bytes();
+        if token as usize == out.tokens.len() {
+            out.tokens.push(Token {
+                id: data.id,
+                special: false,
+                text: String::new(),
+                p: 0.0,
+                plog: 0.0,
+                t0: 0,
+                t1: 0,
+            });
+        }
+        let out = &mut out.tokens[token as usize];
+        out.id = data.id;
+        out.special = data.id >= eot;
+        out.text.clear();
+        if out.special {
+            out.text.push_str(&String::from_utf8_lossy(bytes));
+        } else {
+            push_utf8(&mut pending, bytes, &mut out.text);
+            last_text_token = Some(token as usize);
+        }
+        out.p = data.p;
+        out.plog = data.plog;
+        out.t0 = data.t0;
+        out.t1 = data.t1;
+    }
+    if let Some(last) = last_text_token {
+        // the segment ended in the middle of a character
+        out.tokens[last]
+            .text
+            .push_str(&String::from_utf8_lossy(&pending));
+    }
+    let text = c_str(whisper_rs_sys::whisper_full_get_segment_text(ctx, segment))?.to_str()?;
+    out.text.clear();
+    out.text.push_str(text);
+    out.t0 = whisper_rs_sys::whisper_full_get_segment_t0(ctx, segment);
+    out.t1 = whisper_rs_sys::whisper_full_get_segment_t1(ctx, segment);
+    Ok(())
+}
+
+/// Append the text of a token to `out`.
+///
+/// Tokens are byte-level, so a multi-byte character may be split across several of them.
+/// The bytes of a character that isn't complete yet are kept in `pending`,
+/// and it is appended once a later token completes it. Invalid bytes are replaced with U+FFFD.
+fn push_utf8(pending: &mut Vec<u8>, bytes: &[u8], out: &mut String) {
+    pending.extend_from_slice(bytes);
+    let mut rest = &pending[..];
+    loop {
+        match std::str::from_utf8(rest) {
+            Ok(text) => {
+                out.push_str(text);
+                rest = &[];
+                break;
+            }
+            Err(e) => {
+                let (valid, invalid) = rest.split_at(e.valid_up_to());
+                out.push_str(std::str::from_utf8(valid).unwrap());
+                match e.error_len() {
+                    Some(len) => {
+                        out.push(char::REPLACEMENT_CHARACTER);
+                        rest = &invalid[len..];
+                    }
+                    // only the start of a character, the rest of it is in the next token
+                    None => {
+                        rest = invalid;
+                        break;
+                    }
+                }
+            }
+        }
+    }
+    let consumed = pending.len() - rest.len();
+    pending.drain(..consumed);
+}
+
+/// The logits whisper.cpp keeps after decoding, which are those of the last token only.
+///
+/// # Safety
+/// `ptr` must be null or point to at least `n_vocab` floats that outlive `'a`.
+unsafe fn logits_row<'a>(ptr: *const f32, n_vocab: usize) -> Result<&'a [f32], WhisperError> {
+    if ptr.is_null() {
+        return Err(WhisperError::NullPointer);
+    }
+    Ok(std::slice::from_raw_parts(ptr, n_vocab))
+}
+
+/// # Safety
+/// `ptr` must be null or point to a valid C string that outlives `'a`.
+unsafe fn c_str<'a>(ptr: *const std::ffi::c_char) -> Result<&'a CStr, WhisperError> {
+    if ptr.is_null() {
+        return Err(WhisperError::NullPointer);
+    }
+    Ok(CStr::from_ptr(ptr))
 }
 
 impl Drop for WhisperContext {
@@ -735,3 +1526,41 @@ impl Drop for WhisperContext {
 // concurrent usage is prevented by &mut self on methods that modify the struct
 unsafe impl Send for WhisperContext {}
 unsafe impl Sync for WhisperContext {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn assert_push_utf8() {
+        // "你好" split across three tokens
+        let texts: Vec<String> = [&b" \xe4\xbd"[..], b"\xa0\xe5", b"\xa5\xbd!"]
+            .iter()
+            .scan(Vec::new(), |pending, bytes| {
+                let mut text = String::new();
+                push_utf8(pending, bytes, &mut text);
+                Some(text)
+            })
+            .collect();
+        assert_eq!(texts, [" ", "你", "好!"]);
+
+        let mut pending = Vec::new();
+        let mut text = String::new();
+        push_utf8(&mut pending, b"a\xffb\xe4", &mut text);
+        assert_eq!(text, "a\u{fffd}b");
+        assert_eq!(pending, b"\xe4");
+    }
+
+    #[test]
+    fn assert_logits_are_one_row() {
+        // whisper.cpp resizes its logits to n_vocab after decoding, however many tokens were decoded
+        let n_vocab = 51865;
+        let buffer = vec![0.0; n_vocab];
+        let logits = unsafe { logits_row(buffer.as_ptr(), n_vocab) }.unwrap();
+        assert_eq!(logits.len(), n_vocab);
+        assert!(matches!(
+            unsafe { logits_row(std::ptr::null(), n_vocab) },
+            Err(WhisperError::NullPointer)
+        ));
+    }
+}
@@ -1,33 +1,145 @@
-use std::ffi::{c_float, c_int, CString};
-use std::marker::PhantomData;
-use whisper_rs_sys::whisper_token;
-
+use crate::{Language, WhisperError, WhisperToken};
+use std::ffi::{c_float, c_int, CStr, CString};
+use std::time::Duration;
+
+/// The strategy used to sample tokens from the decoder.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", rename_all = "snake_case")
+)]
 pub enum SamplingStrategy {
+    /// Pick the most likely token at each step.
     Greedy {
+        /// Number of candidates to decode when sampling with a non-zero temperature. Must be at least 1.
         best_of: c_int,
     },
+    /// Keep the `beam_size` most likely sequences at each step.
     BeamSearch {
+        /// Number of beams. Must be at least 1.
         beam_size: c_int,
+        /// Set to -1.0 to disable. Must be -1.0 or positive otherwise.
         // not implemented in whisper.cpp as of this writing (v1.2.0)
         patience: c_float,
     },
 }
 
+/// What to do with the speech in the audio.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Task {
+    /// Write down the speech in the language it is spoken in.
+    #[default]
+    Transcribe,
+    /// Translate the speech to English.
+    Translate,
+}
+
+/// What whisper.cpp prints to stdout while running [WhisperContext::full](crate::WhisperContext::full).
+///
+/// Set all of it at once with [FullParams::set_console_output].
+/// whisper.cpp also logs to stderr while loading a model, which none of these settings affect.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum ConsoleOutput {
+    /// Print nothing, e.g. for TUIs and servers that own stdout.
+    None,
+    /// Print the progress only, as whisper.cpp does by default.
+    Progress,
+    /// Print the progress, and every segment with its timestamps as soon as it is decoded.
+    Realtime,
+}
+
+impl SamplingStrategy {
+    /// Check that the values of this strategy make sense.
+    ///
+    /// This is called by [WhisperContext::full](crate::WhisperContext::full) before running the model.
+    ///
+    /// # Returns
+    /// Ok(()) if the strategy is valid, Err(WhisperError::InvalidSamplingStrategy) otherwise.
+    pub fn validate(&self) -> Result<(), WhisperError> {
+        let valid = match *self {
+            Self::Greedy { best_of } => best_of >= 1,
+            Self::BeamSearch {
+                beam_size,
+                patience,
+            } => beam_size >= 1 && (patience == -1.0 || patience > 0.0),
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(WhisperError::InvalidSamplingStrategy)
+        }
+    }
+}
+
 impl Default for SamplingStrategy {
     fn default() -> Self {
         Self::Greedy { best_of: 1 }
     }
 }
 
-pub struct FullParams<'a, 'b> {
+/// The number of threads [FullParams] uses by default.
+///
+/// This is based on [std::thread::available_parallelism], so it respects CPU affinity and container quotas.
+/// ggml gains next to nothing from a second hyperthread on the same core, so beyond 4 CPUs,
+/// they are assumed to be hyperthreads and only half of them are used, but never fewer than 4.
+/// whisper.cpp's own default of at most 4 threads leaves most of a large machine idle,
+/// and oversubscribes containers limited to fewer CPUs.
+pub fn default_n_threads() -> c_int {
+    threads_for_cpus(std::thread::available_parallelism().map_or(1, |n| n.get()))
+}
+
+fn threads_for_cpus(cpus: usize) -> c_int {
+    // monotonic, so a machine with more CPUs never gets fewer threads
+    let threads = cpus.min(4).max(cpus / 2);
+    threads.clamp(1, c_int::MAX as usize) as c_int
+}
+
+fn processed_duration(n_samples: usize, offset_ms: c_int, duration_ms: c_int) -> Duration {
+    let total_ms = n_samples as u64 * 1000 / whisper_rs_sys::WHISPER_SAMPLE_RATE as u64;
+    let mut processed_ms = total_ms.saturating_sub(offset_ms.max(0) as u64);
+    if duration_ms > 0 {
+        processed_ms = processed_ms.min(duration_ms as u64);
+    }
+    Duration::from_millis(processed_ms)
+}
+
+fn is_too_short(n_samples: usize, duration_ms: c_int, speed_up: bool) -> bool {
+    // mirrors the check at the start of whisper_full, which works in mel frames of 10 ms
+    // (20 ms with speed_up), and ignores the offset
+    let (hop_length, min_frames) = if speed_up { (320, 50) } else { (160, 100) };
+    let frames = if duration_ms == 0 {
+        (n_samples / hop_length) as i64
+    } else {
+        (duration_ms / 10) as i64
+    };
+    frames < min_frames
+}
+
+pub struct FullParams {
     pub(crate) fp: whisper_rs_sys::whisper_full_params,
-    phantom_lang: PhantomData<&'a str>,
-    phantom_tokens: PhantomData<&'b [c_int]>,
+    pub(crate) sampling_strategy: SamplingStrategy,
+    // owned storage for data pointed to by `fp`
+    // heap allocations don't move when FullParams does, so the pointers stay valid
+    pub(crate) language: Option<CString>,
+    tokens: Vec<WhisperToken>,
+    // tokenized by the context when running, as that needs the vocabulary
+    pub(crate) initial_prompt: Option<String>,
 }
 
-impl<'a, 'b> FullParams<'a, 'b> {
+impl FullParams {
     /// Create a new set of parameters for the decoder.
-    pub fn new(sampling_strategy: SamplingStrategy) -> FullParams<'a, 'b> {
+    pub fn new(sampling_strategy: SamplingStrategy) -> FullParams {
         let mut fp = unsafe {
             whisper_rs_sys::whisper_full_default_params(match sampling_strategy {
                 SamplingStrategy::Greedy { .. } => {
@@ -52,20 +164,50 @@ impl<'a, 'b> FullParams<'a, 'b> {
             }
         }
 
+        fp.n_threads = default_n_threads();
+
         Self {
             fp,
-            phantom_lang: PhantomData,
-            phantom_tokens: PhantomData,
+            sampling_strategy,
+            language: None,
+            tokens: Vec::new(),
+            initial_prompt: None,
         }
     }
 
+    /// Start building a set of parameters with a [FullParamsBuilder](crate::FullParamsBuilder),
+    /// which validates them once they are built.
+    ///
+    /// The sampling strategy has no default, so it must be chosen here.
+    pub fn builder(sampling_strategy: SamplingStrategy) -> crate::FullParamsBuilder {
+        crate::FullParamsBuilder::new(sampling_strategy)
+    }
+
+    /// How much of `n_samples` samples of audio whisper.cpp transcribes with these parameters:
+    /// from [offset_ms](FullParams::set_offset_ms) to the end, or for [duration_ms](FullParams::set_duration_ms) if shorter.
+    pub(crate) fn processed_duration(&self, n_samples: usize) -> Duration {
+        processed_duration(n_samples, self.fp.offset_ms, self.fp.duration_ms)
+    }
+
+    /// Does whisper.cpp return without any segments for `n_samples` samples of audio with these parameters?
+    /// See [WhisperError::AudioTooShort].
+    pub(crate) fn is_too_short(&self, n_samples: usize) -> bool {
+        is_too_short(n_samples, self.fp.duration_ms, self.fp.speed_up)
+    }
+
     /// Set the number of threads to use for decoding.
     ///
-    /// Defaults to min(4, std::thread::hardware_concurrency()).
+    /// Defaults to [default_n_threads].
     pub fn set_n_threads(&mut self, n_threads: c_int) {
         self.fp.n_threads = n_threads;
     }
 
+    /// Set the number of threads to use for decoding to [default_n_threads],
+    /// e.g. to undo an earlier call to [FullParams::set_n_threads].
+    pub fn set_n_threads_auto(&mut self) {
+        self.fp.n_threads = default_n_threads();
+    }
+
     /// Max tokens to use from past text as prompt for the decoder
     ///
     /// Defaults to 16384.
@@ -87,13 +229,29 @@ impl<'a, 'b> FullParams<'a, 'b> {
         self.fp.duration_ms = duration_ms;
     }
 
-    /// Set whether to translate the output to the language specified by `language`.
+    /// Set whether to translate the output to English.
     ///
-    /// Defaults to false.
+    /// Defaults to false. See also [FullParams::set_task].
     pub fn set_translate(&mut self, translate: bool) {
         self.fp.translate = translate;
     }
 
+    /// Set the task to perform.
+    ///
+    /// Defaults to [Task::Transcribe].
+    pub fn set_task(&mut self, task: Task) {
+        self.fp.translate = task == Task::Translate;
+    }
+
+    /// The task that will be performed.
+    pub fn task(&self) -> Task {
+        if self.fp.translate {
+            Task::Translate
+        } else {
+            Task::Transcribe
+        }
+    }
+
     /// Do not use past transcription (if any) as initial prompt for the decoder.
     ///
     /// Defaults to false.
@@ -139,6 +297,20 @@ impl<'a, 'b> FullParams<'a, 'b> {
         self.fp.print_timestamps = print_timestamps;
     }
 
+    /// Set everything whisper.cpp prints to stdout at once,
+    /// overriding [set_print_special](FullParams::set_print_special),
+    /// [set_print_progress](FullParams::set_print_progress),
+    /// [set_print_realtime](FullParams::set_print_realtime) and
+    /// [set_print_timestamps](FullParams::set_print_timestamps).
+    ///
+    /// Defaults to [ConsoleOutput::Progress].
+    pub fn set_console_output(&mut self, output: ConsoleOutput) {
+        self.fp.print_special = false;
+        self.fp.print_progress = output != ConsoleOutput::None;
+        self.fp.print_realtime = output == ConsoleOutput::Realtime;
+        self.fp.print_timestamps = output == ConsoleOutput::Realtime;
+    }
+
     /// # EXPERIMENTAL
     ///
     /// Enable token-level timestamps.
@@ -170,6 +342,9 @@ impl<'a, 'b> FullParams<'a, 'b> {
     ///
     /// Set maximum segment length in characters.
     ///
+    /// Segments may be split in the middle of a word. See [Transcript::split_on_words](crate::Transcript::split_on_words)
+    /// to split between words instead.
+    ///
     /// Defaults to 0.
     pub fn set_max_len(&mut self, max_len: c_int) {
         self.fp.max_len = max_len;
@@ -199,6 +374,11 @@ impl<'a, 'b> FullParams<'a, 'b> {
     /// Overwrite the audio context size. 0 = default.
     /// As with [set_speed_up](FullParams::set_speed_up), this can significantly reduce the accuracy of the transcription.
     ///
+    /// Smaller values make the encoder faster, which helps a lot with short clips:
+    /// each unit of audio context covers 20ms of audio, so 1 second of audio needs an audio context of 50.
+    /// Must not be larger than [WhisperContext::n_audio_ctx](crate::WhisperContext::n_audio_ctx),
+    /// otherwise [WhisperContext::full](crate::WhisperContext::full) returns an error.
+    ///
     /// Defaults to 0.
     pub fn set_audio_ctx(&mut self, audio_ctx: c_int) {
         self.fp.audio_ctx = audio_ctx;
@@ -207,34 +387,103 @@ impl<'a, 'b> FullParams<'a, 'b> {
     /// Set tokens to provide the model as initial input.
     ///
     /// These tokens are prepended to any existing text content from a previous call.
+    /// They are copied into the parameters, so the slice does not need to outlive them.
     ///
     /// Calling this more than once will overwrite the previous tokens.
     ///
     /// Defaults to an empty vector.
-    pub fn set_tokens(&mut self, tokens: &'b [c_int]) {
-        // turn into ptr and len
-        let tokens_ptr: *const whisper_token = tokens.as_ptr();
-        let tokens_len: c_int = tokens.len() as c_int;
+    pub fn set_tokens(&mut self, tokens: &[WhisperToken]) {
+        self.tokens = tokens.to_vec();
+        self.fp.prompt_tokens = self.tokens.as_ptr();
+        self.fp.prompt_n_tokens = self.tokens.len() as c_int;
+    }
 
-        // set the tokens
-        self.fp.prompt_tokens = tokens_ptr;
-        self.fp.prompt_n_tokens = tokens_len;
+    /// Set text to provide the model as initial input, e.g. to bias it towards the spelling of names and jargon.
+    ///
+    /// The text is copied into the parameters, and converted into tokens by [WhisperContext::full](crate::WhisperContext::full)
+    /// with the vocabulary of its model. Those tokens replace the ones set with [FullParams::set_tokens].
+    /// Set this to None to use the tokens again.
+    ///
+    /// Defaults to None.
+    pub fn set_initial_prompt(&mut self, initial_prompt: Option<&str>) {
+        self.initial_prompt = initial_prompt.map(str::to_string);
     }
 
     /// Set the target language.
     ///
     /// For auto-detection, set this to either "auto" or None.
     ///
+    /// The string is copied into the parameters, so it does not need to outlive them.
+    ///
     /// Defaults to "en".
-    pub fn set_language(&mut self, language: Option<&'a str>) {
-        self.fp.language = match language {
-            Some(language) => CString::new(language)
-                .expect("Language contains null byte")
-                .into_raw() as *const _,
+    ///
+    /// # Panics
+    /// Panics if the language contains a null byte.
+    pub fn set_language(&mut self, language: Option<&str>) {
+        self.language =
+            language.map(|language| CString::new(language).expect("Language contains null byte"));
+        self.fp.language = match &self.language {
+            Some(language) => language.as_ptr(),
             None => std::ptr::null(),
         };
     }
 
+    /// Set the target language, checking that whisper.cpp knows it.
+    ///
+    /// Like [FullParams::set_language], but unknown codes are rejected instead of being passed on to whisper.cpp.
+    /// For auto-detection, set this to either "auto" or None.
+    ///
+    /// # Returns
+    /// Ok(()) on success, Err(WhisperError::InvalidLanguage) if the language is unknown, in which case nothing is changed.
+    pub fn try_set_language(&mut self, language: Option<&str>) -> Result<(), WhisperError> {
+        match language {
+            Some(code) if code != "auto" && Language::from_code(code).is_none() => {
+                Err(WhisperError::InvalidLanguage)
+            }
+            _ => {
+                self.set_language(language);
+                Ok(())
+            }
+        }
+    }
+
+    /// Set whether to detect the spoken language instead of using the target language.
+    ///
+    /// Enabling this is the same as setting the language to "auto". Disabling it sets the language back to
+    /// the default of "en" if it was set to auto-detection, and leaves any other language alone.
+    /// To only detect the language without transcribing, use [WhisperContext::lang_detect](crate::WhisperContext::lang_detect).
+    ///
+    /// Defaults to false.
+    pub fn set_detect_language(&mut self, detect_language: bool) {
+        if detect_language {
+            self.set_language(Some("auto"));
+        } else if self.detect_language() {
+            self.set_language(Some("en"));
+        }
+    }
+
+    /// Will the spoken language be detected instead of using the target language?
+    pub fn detect_language(&self) -> bool {
+        if self.fp.language.is_null() {
+            return true;
+        }
+        // SAFETY: see FullParams::language
+        let code = unsafe { CStr::from_ptr(self.fp.language) }.to_bytes();
+        // whisper.cpp treats an empty language like "auto"
+        code.is_empty() || code == b"auto"
+    }
+
+    /// The target language, None if it is to be auto-detected.
+    pub fn language(&self) -> Option<Language> {
+        if self.fp.language.is_null() {
+            return None;
+        }
+        // SAFETY: the pointer is either owned by self, or a static string from whisper.cpp's defaults
+        let code = unsafe { CStr::from_ptr(self.fp.language) };
+        // "auto" isn't a language code, so it maps to None as well
+        Language::from_code(code.to_str().ok()?)
+    }
+
     /// Set suppress_blank. See https://github.com/openai/whisper/blob/f82bc59f5ea234d4b97fb2860842ed38519f7e65/whisper/decoding.py#L89
     /// for more information.
     ///
@@ -333,7 +582,7 @@ impl<'a, 'b> FullParams<'a, 'b> {
     /// # Safety
     /// Do not use this function unless you know what you are doing.
     /// * Be careful not to mutate the state of the whisper_context pointer returned in the callback.
-    ///  This could cause undefined behavior, as this violates the thread-safety guarantees of the underlying C library.
+    ///   This could cause undefined behavior, as this violates the thread-safety guarantees of the underlying C library.
     ///
     /// Defaults to None.
     pub unsafe fn set_start_encoder_callback(
@@ -360,5 +609,75 @@ impl<'a, 'b> FullParams<'a, 'b> {
 // following implementations are safe
 // see https://github.com/ggerganov/whisper.cpp/issues/32#issuecomment-1272790388
 // concurrent usage is prevented by &mut self on methods that modify the struct
-unsafe impl<'a, 'b> Send for FullParams<'a, 'b> {}
-unsafe impl<'a, 'b> Sync for FullParams<'a, 'b> {}
+unsafe impl Send for FullParams {}
+unsafe impl Sync for FullParams {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn assert_threads_for_cpus() {
+        assert_eq!(threads_for_cpus(0), 1);
+        assert_eq!(threads_for_cpus(2), 2);
+        assert_eq!(threads_for_cpus(4), 4);
+        assert_eq!(threads_for_cpus(5), 4);
+        assert_eq!(threads_for_cpus(6), 4);
+        assert_eq!(threads_for_cpus(7), 4);
+        assert_eq!(threads_for_cpus(8), 4);
+        assert_eq!(threads_for_cpus(10), 5);
+        assert_eq!(threads_for_cpus(64), 32);
+    }
+
+    #[test]
+    pub fn assert_processed_duration() {
+        let ms = Duration::from_millis;
+        assert_eq!(processed_duration(48000, 0, 0), ms(3000));
+        assert_eq!(processed_duration(48000, 1000, 0), ms(2000));
+        assert_eq!(processed_duration(48000, 1000, 500), ms(500));
+        assert_eq!(processed_duration(48000, 5000, 0), ms(0));
+        assert_eq!(processed_duration(8, 0, 0), ms(0));
+        assert_eq!(processed_duration(8000, 0, 2000), ms(500));
+        assert_eq!(processed_duration(80000, 4500, 0), ms(500));
+    }
+
+    #[test]
+    pub fn assert_too_short() {
+        assert!(is_too_short(15999, 0, false));
+        assert!(!is_too_short(16000, 0, false));
+        // the offset is not taken into account, and duration_ms replaces the length of the audio
+        assert!(!is_too_short(80000, 0, false));
+        assert!(!is_too_short(8000, 2000, false));
+        assert!(is_too_short(80000, 999, false));
+        assert!(is_too_short(80000, -10, false));
+        // with speed_up, frames are twice as long but only half as many are needed
+        assert!(is_too_short(15999, 0, true));
+        assert!(!is_too_short(16000, 0, true));
+        assert!(!is_too_short(80000, 500, true));
+        assert!(is_too_short(80000, 499, true));
+    }
+
+    #[test]
+    pub fn assert_sampling_strategy_validation() {
+        assert!(SamplingStrategy::default().validate().is_ok());
+        assert!(SamplingStrategy::Greedy { best_of: 0 }.validate().is_err());
+        assert!(SamplingStrategy::BeamSearch {
+            beam_size: 5,
+            patience: -1.0
+        }
+        .validate()
+        .is_ok());
+        assert!(SamplingStrategy::BeamSearch {
+            beam_size: 0,
+            patience: -1.0
+        }
+        .validate()
+        .is_err());
+        assert!(SamplingStrategy::BeamSearch {
+            beam_size: 5,
+            patience: 0.0
+        }
+        .validate()
+        .is_err());
+    }
+}
@@ -0,0 +1,302 @@
+use crate::{
+    ConsoleOutput, FullParams, Language, SamplingStrategy, Task, WhisperError, WhisperToken,
+};
+use std::ffi::c_int;
+
+/// Builder for [FullParams] that validates the parameters before handing them out.
+///
+/// Unlike the setters on [FullParams], which accept anything,
+/// [FullParamsBuilder::build] checks that values are in range and work together,
+/// and returns an error describing the first problem it finds.
+///
+/// Create this with [FullParams::builder] or [FullParamsBuilder::new].
+///
+/// ```no_run
+/// # use whisper_rs::{FullParams, SamplingStrategy};
+/// let params = FullParams::builder(SamplingStrategy::Greedy { best_of: 1 })
+///     .language(Some("de"))
+///     .translate(true)
+///     .n_threads(8)
+///     .build()
+///     .expect("invalid parameters");
+/// ```
+pub struct FullParamsBuilder {
+    params: FullParams,
+}
+
+impl FullParamsBuilder {
+    /// Start building parameters with the specified sampling strategy.
+    pub fn new(sampling_strategy: SamplingStrategy) -> Self {
+        Self {
+            params: FullParams::new(sampling_strategy),
+        }
+    }
+
+    /// See [FullParams::set_n_threads].
+    pub fn n_threads(mut self, n_threads: c_int) -> Self {
+        self.params.set_n_threads(n_threads);
+        self
+    }
+
+    /// See [FullParams::set_n_threads_auto].
+    pub fn n_threads_auto(mut self) -> Self {
+        self.params.set_n_threads_auto();
+        self
+    }
+
+    /// See [FullParams::set_n_max_text_ctx].
+    pub fn n_max_text_ctx(mut self, n_max_text_ctx: c_int) -> Self {
+        self.params.set_n_max_text_ctx(n_max_text_ctx);
+        self
+    }
+
+    /// See [FullParams::set_offset_ms].
+    pub fn offset_ms(mut self, offset_ms: c_int) -> Self {
+        self.params.set_offset_ms(offset_ms);
+        self
+    }
+
+    /// See [FullParams::set_duration_ms].
+    pub fn duration_ms(mut self, duration_ms: c_int) -> Self {
+        self.params.set_duration_ms(duration_ms);
+        self
+    }
+
+    /// See [FullParams::set_translate].
+    pub fn translate(mut self, translate: bool) -> Self {
+        self.params.set_translate(translate);
+        self
+    }
+
+    /// See [FullParams::set_task].
+    pub fn task(mut self, task: Task) -> Self {
+        self.params.set_task(task);
+        self
+    }
+
+    /// See [FullParams::set_no_context].
+    pub fn no_context(mut self, no_context: bool) -> Self {
+        self.params.set_no_context(no_context);
+        self
+    }
+
+    /// See [FullParams::set_single_segment].
+    pub fn single_segment(mut self, single_segment: bool) -> Self {
+        self.params.set_single_segment(single_segment);
+        self
+    }
+
+    /// See [FullParams::set_console_output].
+    pub fn console_output(mut self, output: ConsoleOutput) -> Self {
+        self.params.set_console_output(output);
+        self
+    }
+
+    /// See [FullParams::set_print_special].
+    pub fn print_special(mut self, print_special: bool) -> Self {
+        self.params.set_print_special(print_special);
+        self
+    }
+
+    /// See [FullParams::set_print_progress].
+    pub fn print_progress(mut self, print_progress: bool) -> Self {
+        self.params.set_print_progress(print_progress);
+        self
+    }
+
+    /// See [FullParams::set_print_realtime].
+    pub fn print_realtime(mut self, print_realtime: bool) -> Self {
+        self.params.set_print_realtime(print_realtime);
+        self
+    }
+
+    /// See [FullParams::set_print_timestamps].
+    pub fn print_timestamps(mut self, print_timestamps: bool) -> Self {
+        self.params.set_print_timestamps(print_timestamps);
+        self
+    }
+
+    /// See [FullParams::set_token_timestamps].
+    pub fn token_timestamps(mut self, token_timestamps: bool) -> Self {
+        self.params.set_token_timestamps(token_timestamps);
+        self
+    }
+
+    /// See [FullParams::set_thold_pt].
+    pub fn thold_pt(mut self, thold_pt: f32) -> Self {
+        self.params.set_thold_pt(thold_pt);
+        self
+    }
+
+    /// See [FullParams::set_thold_ptsum].
+    pub fn thold_ptsum(mut self, thold_ptsum: f32) -> Self {
+        self.params.set_thold_ptsum(thold_ptsum);
+        self
+    }
+
+    /// See [FullParams::set_max_len].
+    pub fn max_len(mut self, max_len: c_int) -> Self {
+        self.params.set_max_len(max_len);
+        self
+    }
+
+    /// See [FullParams::set_max_tokens].
+    pub fn max_tokens(mut self, max_tokens: c_int) -> Self {
+        self.params.set_max_tokens(max_tokens);
+        self
+    }
+
+    /// See [FullParams::set_speed_up].
+    pub fn speed_up(mut self, speed_up: bool) -> Self {
+        self.params.set_speed_up(speed_up);
+        self
+    }
+
+    /// See [FullParams::set_audio_ctx].
+    pub fn audio_ctx(mut self, audio_ctx: c_int) -> Self {
+        self.params.set_audio_ctx(audio_ctx);
+        self
+    }
+
+    /// See [FullParams::set_suppress_blank].
+    pub fn suppress_blank(mut self, suppress_blank: bool) -> Self {
+        self.params.set_suppress_blank(suppress_blank);
+        self
+    }
+
+    /// See [FullParams::set_temperature].
+    pub fn temperature(mut self, temperature: f32) -> Self {
+        self.params.set_temperature(temperature);
+        self
+    }
+
+    /// See [FullParams::set_max_initial_ts].
+    pub fn max_initial_ts(mut self, max_initial_ts: f32) -> Self {
+        self.params.set_max_initial_ts(max_initial_ts);
+        self
+    }
+
+    /// See [FullParams::set_length_penalty].
+    pub fn length_penalty(mut self, length_penalty: f32) -> Self {
+        self.params.set_length_penalty(length_penalty);
+        self
+    }
+
+    /// See [FullParams::set_temperature_inc].
+    pub fn temperature_inc(mut self, temperature_inc: f32) -> Self {
+        self.params.set_temperature_inc(temperature_inc);
+        self
+    }
+
+    /// See [FullParams::set_entropy_thold].
+    pub fn entropy_thold(mut self, entropy_thold: f32) -> Self {
+        self.params.set_entropy_thold(entropy_thold);
+        self
+    }
+
+    /// See [FullParams::set_logprob_thold].
+    pub fn logprob_thold(mut self, logprob_thold: f32) -> Self {
+        self.params.set_logprob_thold(logprob_thold);
+        self
+    }
+
+    /// See [FullParams::set_no_speech_thold].
+    pub fn no_speech_thold(mut self, no_speech_thold: f32) -> Self {
+        self.params.set_no_speech_thold(no_speech_thold);
+        self
+    }
+
+    /// See [FullParams::set_tokens].
+    pub fn tokens(mut self, tokens: &[WhisperToken]) -> Self {
+        self.params.set_tokens(tokens);
+        self
+    }
+
+    /// See [FullParams::set_initial_prompt].
+    pub fn initial_prompt(mut self, initial_prompt: Option<&str>) -> Self {
+        self.params.set_initial_prompt(initial_prompt);
+        self
+    }
+
+    /// See [FullParams::set_language].
+    ///
+    /// Unlike the setter, [FullParamsBuilder::build] checks that the language is known to whisper.cpp.
+    pub fn language(mut self, language: Option<&str>) -> Self {
+        self.params.set_language(language);
+        self
+    }
+
+    /// Validate the parameters and build them.
+    ///
+    /// # Returns
+    /// Ok(FullParams) on success, Err(WhisperError) describing the first invalid parameter on failure.
+    pub fn build(self) -> Result<FullParams, WhisperError> {
+        let fp = &self.params.fp;
+        self.params.sampling_strategy.validate()?;
+        if fp.n_threads < 1 {
+            return Err(WhisperError::InvalidThreadCount);
+        }
+        if let Some(language) = &self.params.language {
+            let language = language.to_str()?;
+            if language != "auto" && Language::from_code(language).is_none() {
+                return Err(WhisperError::InvalidLanguage);
+            }
+        }
+
+        if let Some(idx) = self
+            .params
+            .initial_prompt
+            .as_deref()
+            .and_then(|prompt| prompt.find('\0'))
+        {
+            return Err(WhisperError::NullByteInString { idx });
+        }
+
+        let non_negative: [(&'static str, c_int); 6] = [
+            ("n_max_text_ctx", fp.n_max_text_ctx),
+            ("offset_ms", fp.offset_ms),
+            ("duration_ms", fp.duration_ms),
+            ("max_len", fp.max_len),
+            ("max_tokens", fp.max_tokens),
+            ("audio_ctx", fp.audio_ctx),
+        ];
+        for (name, value) in non_negative {
+            if value < 0 {
+                return Err(WhisperError::InvalidParameter { name });
+            }
+        }
+        let probabilities = [
+            ("thold_pt", fp.thold_pt),
+            ("thold_ptsum", fp.thold_ptsum),
+            ("no_speech_thold", fp.no_speech_thold),
+        ];
+        for (name, value) in probabilities {
+            if !(0.0..=1.0).contains(&value) {
+                return Err(WhisperError::InvalidParameter { name });
+            }
+        }
+        let non_negative_finite = [
+            ("temperature", fp.temperature),
+            ("temperature_inc", fp.temperature_inc),
+            ("max_initial_ts", fp.max_initial_ts),
+        ];
+        for (name, value) in non_negative_finite {
+            if !value.is_finite() || value < 0.0 {
+                return Err(WhisperError::InvalidParameter { name });
+            }
+        }
+        // infinities are fine here, they disable the threshold
+        let not_nan = [
+            ("length_penalty", fp.length_penalty),
+            ("entropy_thold", fp.entropy_thold),
+            ("logprob_thold", fp.logprob_thold),
+        ];
+        for (name, value) in not_nan {
+            if value.is_nan() {
+                return Err(WhisperError::InvalidParameter { name });
+            }
+        }
+
+        Ok(self.params)
+    }
+}
@@ -4,6 +4,7 @@ extern crate bindgen;
 
 use std::env;
 use std::path::PathBuf;
+use std::process::Command;
 
 fn main() {
     let target = env::var("TARGET").unwrap();
@@ -15,10 +16,66 @@ fn main() {
     if target.contains("apple") {
         println!("cargo:rustc-link-lib=framework=Accelerate");
     }
+    // Link OpenBLAS for matrix calculations on other platforms
+    if cfg!(feature = "openblas") {
+        // OpenBLAS is rarely on the default search path on Windows, so allow pointing at it
+        println!("cargo:rerun-if-env-changed=OPENBLAS_PATH");
+        if let Ok(openblas_path) = env::var("OPENBLAS_PATH") {
+            println!(
+                "cargo:rustc-link-search={}",
+                PathBuf::from(openblas_path).join("lib").display()
+            );
+        }
+        println!("cargo:rustc-link-lib=openblas");
+    }
 
-    println!("cargo:rustc-link-search={}", env::var("OUT_DIR").unwrap());
-    println!("cargo:rustc-link-lib=static=whisper");
+    // Link an existing libwhisper instead of building the bundled one, e.g. one provided by a distro or Nix.
+    // It has to be built from the same whisper.cpp version, as the bindings are generated from the bundled headers.
+    println!("cargo:rerun-if-env-changed=WHISPER_LIB_DIR");
+    println!("cargo:rerun-if-env-changed=WHISPER_NO_BUILD");
+    let lib_dir = env::var_os("WHISPER_LIB_DIR");
+    let prebuilt = lib_dir.is_some() || env::var_os("WHISPER_NO_BUILD").is_some();
+    if let Some(lib_dir) = &lib_dir {
+        println!(
+            "cargo:rustc-link-search=native={}",
+            PathBuf::from(lib_dir).display()
+        );
+    }
+    if prebuilt {
+        // let the linker pick whichever of the static and shared library is there
+        println!("cargo:rustc-link-lib=whisper");
+    } else {
+        // where `cmake --install` puts the library, see below
+        let install_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+        if cfg!(feature = "dynamic-link") {
+            println!(
+                "cargo:rustc-link-search={}",
+                install_dir.join("lib").display()
+            );
+            if target.contains("windows") {
+                // the import library of the DLL is installed as an archive,
+                // and adding the DLL's directory lets `cargo run` and `cargo test` find it
+                println!(
+                    "cargo:rustc-link-search={}",
+                    install_dir.join("lib/static").display()
+                );
+                println!(
+                    "cargo:rustc-link-search={}",
+                    install_dir.join("bin").display()
+                );
+            }
+            println!("cargo:rustc-link-lib=dylib=whisper");
+        } else {
+            println!(
+                "cargo:rustc-link-search={}",
+                install_dir.join("lib/static").display()
+            );
+            println!("cargo:rustc-link-lib=static=whisper");
+        }
+    }
     println!("cargo:rerun-if-changed=wrapper.h");
+    // set when the bindings were generated from the headers, rather than copied from the bundled ones
+    println!("cargo:rustc-check-cfg=cfg(whisper_generated_bindings)");
 
     if env::var("WHISPER_DONT_GENERATE_BINDINGS").is_ok() {
         let _: u64 = std::fs::copy(
@@ -30,6 +87,10 @@ fn main() {
         let bindings = bindgen::Builder::default()
             .header("wrapper.h")
             .clang_arg("-I./whisper.cpp")
+            // only the whisper API, not the system headers it includes, which differ between platforms
+            .allowlist_function("whisper_.*")
+            .allowlist_type("whisper_.*")
+            .allowlist_var("WHISPER_.*")
             .parse_callbacks(Box::new(bindgen::CargoCallbacks))
             .generate();
 
@@ -38,6 +99,7 @@ fn main() {
                 let out_path = PathBuf::from(env::var("OUT_DIR").unwrap());
                 b.write_to_file(out_path.join("bindings.rs"))
                     .expect("Couldn't write bindings!");
+                println!("cargo:rustc-cfg=whisper_generated_bindings");
             }
             Err(e) => {
                 println!("cargo:warning=Unable to generate bindings: {}", e);
@@ -52,69 +114,181 @@ fn main() {
         }
     };
 
-    // stop if we're on docs.rs
-    if env::var("DOCS_RS").is_ok() {
+    // stop if we're on docs.rs, or the library doesn't need to be built
+    if env::var("DOCS_RS").is_ok() || prebuilt {
         return;
     }
 
-    // build libwhisper.a
-    env::set_current_dir("whisper.cpp").expect("Unable to change directory to whisper.cpp");
-    _ = std::fs::remove_dir_all("build");
-    _ = std::fs::create_dir("build");
-    env::set_current_dir("build").expect("Unable to change directory to whisper.cpp build");
+    // Build and install libwhisper in OUT_DIR, keeping the cmake build directory between runs so rebuilds are incremental.
+    // Nothing is written to the crate source, so it may be read-only.
+    let whisper_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap()).join("whisper.cpp");
+    println!("cargo:rerun-if-changed={}", whisper_dir.display());
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    let build_dir = out_dir.join("build");
+    std::fs::create_dir_all(&build_dir).expect("Unable to create whisper.cpp build directory");
 
-    let code = std::process::Command::new("cmake")
-        .arg("..")
+    // whisper.cpp writes files into its source directory when it is configured as the top-level project,
+    // so include it from a project of our own instead
+    let project_dir = out_dir.join("project");
+    std::fs::create_dir_all(&project_dir).expect("Unable to create cmake project directory");
+    std::fs::write(
+        project_dir.join("CMakeLists.txt"),
+        format!(
+            "cmake_minimum_required(VERSION 3.0)\n\
+             project(whisper-rs-sys C CXX)\n\
+             add_subdirectory(\"{}\" whisper)\n",
+            whisper_dir.display().to_string().replace('\\', "/")
+        ),
+    )
+    .expect("Unable to write cmake project");
+
+    let mut cmd = Command::new("cmake");
+    cmd.current_dir(&build_dir)
+        .arg(&project_dir)
+        .arg(format!(
+            "-DCMAKE_INSTALL_PREFIX={}",
+            out_dir.display().to_string().replace('\\', "/")
+        ))
         .arg("-DCMAKE_BUILD_TYPE=Release")
-        .arg("-DBUILD_SHARED_LIBS=OFF")
+        .arg(if cfg!(feature = "dynamic-link") {
+            "-DBUILD_SHARED_LIBS=ON"
+        } else {
+            "-DBUILD_SHARED_LIBS=OFF"
+        })
         .arg("-DWHISPER_ALL_WARNINGS=OFF")
         .arg("-DWHISPER_ALL_WARNINGS_3RD_PARTY=OFF")
         .arg("-DWHISPER_BUILD_TESTS=OFF")
-        .arg("-DWHISPER_BUILD_EXAMPLES=OFF")
-        .status()
-        .expect("Failed to generate build script");
+        .arg("-DWHISPER_BUILD_EXAMPLES=OFF");
+
+    if cfg!(feature = "openblas") {
+        cmd.arg("-DWHISPER_SUPPORT_OPENBLAS=ON");
+    }
+    configure_cpu_features(&mut cmd, &target);
+    if target.contains("android") {
+        configure_android(&mut cmd, &target);
+    }
+    if target.contains("emscripten") {
+        configure_emscripten(&mut cmd);
+    } else if target.starts_with("wasm") {
+        panic!(
+            "whisper.cpp needs pthreads, so {} isn't supported; use wasm32-unknown-emscripten",
+            target
+        );
+    }
+    if cfg!(feature = "dynamic-link") && target.contains("windows") {
+        // whisper.h only exports its functions from the DLL when this is defined
+        cmd.arg("-DCMAKE_C_FLAGS_INIT=-DWHISPER_BUILD")
+            .arg("-DCMAKE_CXX_FLAGS_INIT=-DWHISPER_BUILD");
+    }
+
+    let code = cmd.status().expect("Failed to generate build script");
     if code.code() != Some(0) {
         panic!("Failed to generate build script");
     }
 
-    let code = std::process::Command::new("cmake")
+    // installing puts the library in the same place for every platform and generator
+    let code = Command::new("cmake")
+        .current_dir(&build_dir)
         .arg("--build")
         .arg(".")
-        .arg("--config Release")
+        .args(["--config", "Release"])
+        .args(["--target", "install"])
         .status()
-        .expect("Failed to build libwhisper.a");
+        .expect("Failed to build libwhisper");
     if code.code() != Some(0) {
-        panic!("Failed to build libwhisper.a");
+        panic!("Failed to build libwhisper");
     }
+}
 
-    // move libwhisper.a to where Cargo expects it (OUT_DIR)
-    #[cfg(target_os = "windows")]
-    {
-        std::fs::copy(
-            "Release/whisper.lib",
-            format!("{}/whisper.lib", env::var("OUT_DIR").unwrap()),
-        )
-        .expect("Failed to copy libwhisper.a");
+/// Leave out x86 instruction set extensions that are disabled with `WHISPER_NO_*` environment variables,
+/// so the library runs on older CPUs. They are all used by default.
+fn configure_cpu_features(cmd: &mut Command, target: &str) {
+    let disabled = |name: &str| {
+        println!("cargo:rerun-if-env-changed={}", name);
+        env::var_os(name).is_some()
+    };
+    let no_avx = disabled("WHISPER_NO_AVX");
+    // the other extensions are all encoded with AVX
+    let no_avx2 = disabled("WHISPER_NO_AVX2") || no_avx;
+    let no_fma = disabled("WHISPER_NO_FMA") || no_avx;
+    let no_f16c = disabled("WHISPER_NO_F16C") || no_avx;
+    if !(target.starts_with("x86_64") || target.starts_with("i686")) {
+        return;
     }
-
-    #[cfg(not(target_os = "windows"))]
-    {
-        std::fs::copy(
-            "libwhisper.a",
-            format!("{}/libwhisper.a", env::var("OUT_DIR").unwrap()),
-        )
-        .expect("Failed to copy libwhisper.a");
+    if target.contains("msvc") {
+        if no_avx2 || no_fma || no_f16c {
+            println!("cargo:warning=WHISPER_NO_* are ignored with MSVC, which always builds whisper.cpp with AVX2");
+        }
+        return;
     }
 
-    // clean the whisper build directory to prevent Cargo from complaining during crate publish
-    _ = std::fs::remove_dir_all("build");
+    // always pass every option, as cmake keeps their values in the reused build directory
+    let on_off = |off| if off { "ON" } else { "OFF" };
+    cmd.arg(format!("-DWHISPER_NO_AVX={}", on_off(no_avx)))
+        .arg(format!("-DWHISPER_NO_AVX2={}", on_off(no_avx2)))
+        .arg(format!("-DWHISPER_NO_FMA={}", on_off(no_fma)));
+    // whisper.cpp always adds -mf16c, but the flags of the build type come after it
+    cmd.arg(if no_f16c {
+        "-DCMAKE_C_FLAGS_RELEASE=-O3 -DNDEBUG -mno-f16c"
+    } else {
+        "-DCMAKE_C_FLAGS_RELEASE=-O3 -DNDEBUG"
+    });
+}
+
+/// Cross-compile with the toolchain of the Android NDK, which has to be installed.
+fn configure_android(cmd: &mut Command, target: &str) {
+    println!("cargo:rerun-if-env-changed=ANDROID_NDK_HOME");
+    println!("cargo:rerun-if-env-changed=ANDROID_NDK_ROOT");
+    println!("cargo:rerun-if-env-changed=ANDROID_PLATFORM");
+    let ndk = env::var("ANDROID_NDK_HOME")
+        .or_else(|_| env::var("ANDROID_NDK_ROOT"))
+        .expect("Set ANDROID_NDK_HOME to the Android NDK directory to build for Android");
+    let abi = match target.split('-').next().unwrap() {
+        "aarch64" => "arm64-v8a",
+        "armv7" | "thumbv7neon" => "armeabi-v7a",
+        "i686" => "x86",
+        "x86_64" => "x86_64",
+        arch => panic!("Unsupported Android architecture {}", arch),
+    };
+    // the oldest API level supported by all ABIs of current NDKs
+    let platform = env::var("ANDROID_PLATFORM").unwrap_or_else(|_| "android-21".to_string());
+
+    cmd.arg(format!(
+        "-DCMAKE_TOOLCHAIN_FILE={}",
+        PathBuf::from(ndk)
+            .join("build/cmake/android.toolchain.cmake")
+            .display()
+    ))
+    .arg(format!("-DANDROID_ABI={}", abi))
+    .arg(format!("-DANDROID_PLATFORM={}", platform))
+    // must match the C++ standard library linked by get_cpp_link_stdlib
+    .arg("-DANDROID_STL=c++_shared");
+}
+
+/// Cross-compile with the toolchain of Emscripten, which has to be installed and activated.
+///
+/// whisper.cpp enables pthreads and WebAssembly SIMD by itself for Emscripten.
+fn configure_emscripten(cmd: &mut Command) {
+    println!("cargo:rerun-if-env-changed=EMSDK");
+    let emsdk = env::var("EMSDK")
+        .expect("Activate the Emscripten SDK (`source emsdk_env.sh`) to build for wasm32-unknown-emscripten");
+    cmd.arg(format!(
+        "-DCMAKE_TOOLCHAIN_FILE={}",
+        PathBuf::from(emsdk)
+            .join("upstream/emscripten/cmake/Modules/Platform/Emscripten.cmake")
+            .display()
+    ));
 }
 
 // From https://github.com/alexcrichton/cc-rs/blob/fba7feded71ee4f63cfe885673ead6d7b4f2f454/src/lib.rs#L2462
 fn get_cpp_link_stdlib(target: &str) -> Option<&'static str> {
     if target.contains("msvc") {
         None
-    } else if target.contains("apple") || target.contains("freebsd") || target.contains("openbsd") {
+    } else if target.contains("apple")
+        || target.contains("freebsd")
+        || target.contains("openbsd")
+        || target.contains("emscripten")
+    {
         Some("c++")
     } else if target.contains("android") {
         Some("c++_shared")
@@ -1,5 +1,33 @@
 #![allow(non_upper_case_globals)]
 #![allow(non_camel_case_types)]
 #![allow(non_snake_case)]
+#![allow(clippy::empty_docs)]
 
 include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
+
+#[cfg(test)]
+mod test {
+    /// The bundled bindings are used on docs.rs and whenever generating them fails,
+    /// so check they match the ones generated from the bundled headers.
+    /// They were generated on 64 bit Linux, where the layout tests match.
+    /// This only runs when the build script generated the bindings, as they are a copy of the bundled ones otherwise,
+    /// e.g. with `WHISPER_DONT_GENERATE_BINDINGS` set.
+    ///
+    /// If this fails, update them with the generated ones from `target/*/build/whisper-rs-sys-*/out/bindings.rs`.
+    #[test]
+    #[cfg(all(
+        whisper_generated_bindings,
+        target_os = "linux",
+        target_pointer_width = "64"
+    ))]
+    fn assert_bundled_bindings_are_up_to_date() {
+        // the first line names the bindgen version
+        let skip_header = |bindings: &'static str| bindings.lines().skip(1).collect::<Vec<_>>();
+        let generated = include_str!(concat!(env!("OUT_DIR"), "/bindings.rs"));
+        let bundled = include_str!("bindings.rs");
+        assert!(
+            skip_header(generated) == skip_header(bundled),
+            "sys/src/bindings.rs is out of date"
+        );
+    }
+}